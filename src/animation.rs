@@ -0,0 +1,226 @@
+// src/animation.rs
+//
+// Short animations for line clears, locks and hard drops. The board only notes what happened and on
+// which frame, and drawing works out how far along each animation is from the frame count, so they run
+// on the tick loop, stand still while the game is paused and play back the same in replays. Cleared
+// rows flash for the first half of the line clear delay and close up one by one in the second half,
+// rule sets without a delay get `CLEAR_FRAMES` for it instead, and the next piece is drawn where it
+// really is over the closing rows. `no_animations` in the config turns
+// them all off, but not the labels naming each clear, which stay up for `ACTION_FRAMES` and fade out
+// in the last third of that.
+
+use crate::block::{Block, Row};
+use crate::shape::Shape;
+
+/// how long a line clear is animated when the rules don't give it a delay
+const CLEAR_FRAMES: u32 = 12;
+/// frames cleared rows stay lit, and then dark, while they flash
+const FLASH_FRAMES: u64 = 3;
+const LOCK_FRAMES: u64 = 6;
+const TRAIL_FRAMES: u64 = 8;
+const ACTION_FRAMES: u64 = 90;
+
+#[derive(Clone, Default)]
+pub struct Animations {
+    clear: Option<ClearAnimation>,
+    /// the frame the last piece locked on, and where
+    lock: Option<(u64, Vec<(usize, usize)>)>,
+    trail: Option<Trail>,
+    /// the frame of the last clear or spin, and what it's called
+    action: Option<(u64, Vec<String>)>,
+}
+
+#[derive(Clone)]
+struct ClearAnimation {
+    frame: u64,
+    frames: u32,
+    /// the cleared rows from top to bottom, each with where it was on the board
+    rows: Vec<(usize, Row)>,
+}
+
+/// the last hard drop
+#[derive(Clone)]
+struct Trail {
+    frame: u64,
+    shape: Shape,
+    /// each column the piece fell down, from the row it started in to the row it landed on
+    columns: Vec<(usize, usize, usize)>,
+}
+
+impl Animations {
+    /// `rows` were cleared on `frame`, and the next piece waits `delay` frames for them
+    pub fn cleared(&mut self, frame: u64, delay: u32, rows: Vec<(usize, Row)>) {
+        let frames = if delay > 0 { delay } else { CLEAR_FRAMES };
+        self.clear = Some(ClearAnimation { frame, frames, rows });
+    }
+
+    pub fn locked(&mut self, frame: u64, blocks: &[(usize, usize, Shape)]) {
+        self.lock = Some((frame, blocks.iter().map(|(x, y, _)| (*x, *y)).collect()));
+    }
+
+    /// a piece was hard dropped from `from` to `to` on `frame`
+    pub fn dropped(&mut self, frame: u64, from: &[(usize, usize, Shape)], to: &[(usize, usize, Shape)]) {
+        let Some((_, _, shape)) = to.first() else {
+            return;
+        };
+        let mut columns: Vec<(usize, usize, usize)> = Vec::new();
+        for (x, y, _) in to {
+            let top = from.iter().filter(|(from_x, ..)| from_x == x).map(|(_, from_y, _)| *from_y).min().unwrap_or(*y);
+            match columns.iter_mut().find(|(column, ..)| column == x) {
+                Some(column) => column.2 = column.2.min(*y),
+                None => columns.push((*x, top, *y)),
+            }
+        }
+        self.trail = Some(Trail { frame, shape: *shape, columns });
+    }
+
+    pub fn acted(&mut self, frame: u64, labels: Vec<String>) {
+        self.action = Some((frame, labels));
+    }
+
+    /// the labels for the last clear while they're up, and whether they're fading out
+    pub fn action(&self, now: u64) -> Option<(&[String], bool)> {
+        let (frame, labels) = self.action.as_ref()?;
+        let elapsed = now.saturating_sub(*frame);
+        (elapsed < ACTION_FRAMES).then_some((labels.as_slice(), elapsed >= ACTION_FRAMES * 2 / 3))
+    }
+
+    /// the rows to draw while a line clear is animating, each with whether it's lit up, or `None` once
+    /// the rows are gone
+    pub fn clearing(&self, board: &[Row], now: u64) -> Option<Vec<(Row, bool)>> {
+        let clear = self.clear.as_ref()?;
+        let elapsed = now.checked_sub(clear.frame)?;
+        let frames = clear.frames as u64;
+        if elapsed >= frames {
+            return None;
+        }
+
+        let half = (frames / 2).max(1);
+        let flashing = elapsed < half;
+        let lit = flashing && (elapsed / FLASH_FRAMES).is_multiple_of(2);
+        // the bottom rows close up first
+        let count = clear.rows.len() as u64;
+        let shown = match flashing {
+            true => count,
+            false => (count * (frames - elapsed)).div_ceil(frames - half),
+        } as usize;
+
+        // the board after the clear has an empty row on top for each cleared row, taking the shown
+        // rows' worth of them off puts the rows above the shown rows back where they were
+        let gap = clear.rows.len() - shown;
+        let mut rows = board[shown..].iter().map(|row| (without_active(row), false)).collect::<Vec<(Row, bool)>>();
+        for (y, row) in &clear.rows[..shown] {
+            let row = if flashing { row.clone() } else { Row::empty(row.cells.len()) };
+            rows.insert(y + gap, (row, lit));
+        }
+
+        // a piece that spawned during the clear stays where it is on the board, like its ghost
+        for (y, row) in board.iter().enumerate() {
+            for (x, cell) in row.cells.iter().enumerate() {
+                if let Block::Active(_) = cell {
+                    rows[y].0.cells[x] = *cell;
+                }
+            }
+        }
+        Some(rows)
+    }
+
+    /// whether the block at `(x, y)` is flashing because it just locked. a line clear has its own
+    /// flash, so locks that cleared lines don't get one
+    pub fn locking(&self, x: usize, y: usize, now: u64) -> bool {
+        let clearing = self.clear.as_ref().is_some_and(|clear| now.saturating_sub(clear.frame) < clear.frames as u64);
+        match &self.lock {
+            Some((frame, blocks)) if !clearing && now.saturating_sub(*frame) < LOCK_FRAMES => blocks.contains(&(x, y)),
+            _ => false,
+        }
+    }
+
+    /// the dropped piece if `(x, y)` is part of its trail, which shrinks down towards where it landed
+    pub fn trail(&self, x: usize, y: usize, now: u64) -> Option<Shape> {
+        let trail = self.trail.as_ref()?;
+        let elapsed = now.saturating_sub(trail.frame);
+        if elapsed >= TRAIL_FRAMES {
+            return None;
+        }
+        let (_, top, bottom) = trail.columns.iter().find(|(column, ..)| *column == x)?;
+        let top = top + (bottom - top) * elapsed as usize / TRAIL_FRAMES as usize;
+        (top..*bottom).contains(&y).then_some(trail.shape)
+    }
+}
+
+fn without_active(row: &Row) -> Row {
+    let mut row = row.clone();
+    for cell in row.cells.iter_mut() {
+        if let Block::Active(_) = cell {
+            *cell = Block::Empty;
+        }
+    }
+    row
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(c: Option<char>) -> Row {
+        let mut row = Row::empty(2);
+        if let Some(c) = c {
+            row.cells[0] = Block::Full(Shape::from_char(c).unwrap());
+        }
+        row
+    }
+
+    /// each row as the letter in its first cell, '.' for empty, and whether it's lit
+    fn letters(rows: &[(Row, bool)]) -> Vec<(char, bool)> {
+        rows.iter()
+            .map(|(row, lit)| match row.cells[0] {
+                Block::Full(shape) => (shape.to_char(), *lit),
+                Block::Active(shape) => (shape.to_char().to_ascii_lowercase(), *lit),
+                _ => ('.', *lit),
+            })
+            .collect()
+    }
+
+    /// "S I Z O" from the top with I and O cleared, leaving ". . S Z"
+    fn cleared() -> (Animations, Vec<Row>) {
+        let mut animations = Animations::default();
+        animations.cleared(100, 12, vec![(1, row(Some('I'))), (3, row(Some('O')))]);
+        (animations, vec![row(None), row(None), row(Some('S')), row(Some('Z'))])
+    }
+
+    #[test]
+    fn cleared_rows_flash_where_they_were() {
+        let (animations, board) = cleared();
+        let rows = animations.clearing(&board, 100).unwrap();
+        assert_eq!(letters(&rows), [('S', false), ('I', true), ('Z', false), ('O', true)]);
+        let rows = animations.clearing(&board, 103).unwrap();
+        assert_eq!(letters(&rows), [('S', false), ('I', false), ('Z', false), ('O', false)]);
+    }
+
+    #[test]
+    fn gaps_close_from_the_bottom() {
+        let (animations, board) = cleared();
+        let rows = animations.clearing(&board, 106).unwrap();
+        assert_eq!(letters(&rows), [('S', false), ('.', false), ('Z', false), ('.', false)]);
+        let rows = animations.clearing(&board, 109).unwrap();
+        assert_eq!(letters(&rows), [('.', false), ('S', false), ('.', false), ('Z', false)]);
+        assert!(animations.clearing(&board, 112).is_none());
+    }
+
+    #[test]
+    fn the_next_piece_stays_where_it_is() {
+        let (animations, mut board) = cleared();
+        board[0].cells[0] = Block::Active(Shape::from_char('T').unwrap());
+        let rows = animations.clearing(&board, 100).unwrap();
+        assert_eq!(letters(&rows), [('t', false), ('I', true), ('Z', false), ('O', true)]);
+        let rows = animations.clearing(&board, 109).unwrap();
+        assert_eq!(letters(&rows), [('t', false), ('S', false), ('.', false), ('Z', false)]);
+    }
+
+    #[test]
+    fn nothing_to_draw_before_or_without_a_clear() {
+        let (animations, board) = cleared();
+        assert!(animations.clearing(&board, 99).is_none());
+        assert!(Animations::default().clearing(&board, 0).is_none());
+    }
+}
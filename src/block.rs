@@ -1,33 +1,35 @@
 // src/block.rs
 
 use crate::shape::Shape;
-use crate::constants::ROW_WIDTH;
 
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Block {
     Full(Shape),
     Empty,
     Active(Shape),
+    /// locked block that didn't come from a piece, e.g. preset in a puzzle
+    Garbage,
 }
 
 impl Block {
-    pub fn repr(&self) -> String {
-        match self {
-            Block::Full(_) | Block::Active(_) => "██",
-            Block::Empty => ". ",
-        }.to_string()
-    }    
-
     pub fn is_full(&self) -> bool {
-        match self {
-            Block::Full(_) => true,
-            _ => false,
-        }
+        matches!(self, Block::Full(_) | Block::Garbage)
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 pub struct Row {
-    pub cells: [Block; ROW_WIDTH],
+    pub cells: Vec<Block>,
+    /// frame on which each cell was locked, used by the fading modifier
+    pub locked_at: Vec<u64>,
+}
+
+impl Row {
+    pub fn empty(width: usize) -> Self {
+        Row {
+            cells: vec![Block::Empty; width],
+            locked_at: vec![0; width],
+        }
+    }
 }
 
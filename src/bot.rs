@@ -0,0 +1,254 @@
+// src/bot.rs
+//
+// The bot plays on a `Grid` of its own through the same actions a player's keys map to. For each
+// new piece it tries every rotation and column, hard drops a copy of the board for each one and
+// scores the result, looking ahead through the next pieces in the queue as deep as it's allowed to.
+// It then plays the inputs for the best placement, spread out so it never beats its pieces per
+// second cap. Every so often it makes a mistake and goes for a worse placement instead.
+
+use std::collections::{HashSet, VecDeque};
+
+use clap::ValueEnum;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::grid::Grid;
+use crate::speed::FRAMES_PER_SECOND;
+use crate::versus::Action;
+
+/// weights for scoring a board, the stack shape ones are from Yiyuan Lee's tetris AI
+const HEIGHT_WEIGHT: f32 = -0.51;
+const HOLE_WEIGHT: f32 = -0.36;
+const BUMPINESS_WEIGHT: f32 = -0.18;
+const LINE_WEIGHT: f32 = 0.76;
+const ATTACK_WEIGHT: f32 = 1.0;
+/// a mistake picks one of this many placements after the best one
+const MISTAKE_CHOICES: usize = 5;
+/// only this many of the best looking placements are looked into any deeper
+const BEAM_WIDTH: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+}
+
+impl Difficulty {
+    pub fn settings(&self) -> BotSettings {
+        match self {
+            Difficulty::Easy => BotSettings {
+                pps: 0.8,
+                depth: 1,
+                error_rate: 0.2,
+            },
+            Difficulty::Medium => BotSettings {
+                pps: 1.5,
+                depth: 2,
+                error_rate: 0.05,
+            },
+            Difficulty::Hard => BotSettings {
+                pps: 3.0,
+                depth: 2,
+                error_rate: 0.0,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct BotSettings {
+    /// most pieces placed per second
+    pub pps: f32,
+    /// how many pieces the search looks at, the current one included
+    pub depth: u32,
+    /// chance from 0 to 1 of going for a worse placement than the best one
+    pub error_rate: f32,
+}
+
+/// a way to place the active piece and the board it leaves behind
+struct Placement {
+    actions: Vec<Action>,
+    grid: Grid,
+    topped_out: bool,
+}
+
+pub struct Bot {
+    pub settings: BotSettings,
+    plan: VecDeque<Action>,
+    /// frames between the actions of the current plan
+    gap: u32,
+    cooldown: u32,
+    rng: StdRng,
+}
+
+impl Bot {
+    pub fn new(settings: BotSettings, seed: u64) -> Self {
+        Bot {
+            settings,
+            plan: VecDeque::new(),
+            gap: 0,
+            cooldown: 0,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// plays this frame's input on the bot's board, if it has one due
+    pub fn act(&mut self, grid: &mut Grid, end_cb: impl FnMut(Grid)) {
+        if grid.active_shape.is_none() || grid.paused {
+            return;
+        }
+
+        if self.plan.is_empty() {
+            self.plan = self.choose(grid).into();
+            let piece_frames = FRAMES_PER_SECOND as f32 / self.settings.pps.max(0.01);
+            self.gap = (piece_frames / self.plan.len().max(1) as f32).ceil() as u32;
+            self.cooldown = self.gap;
+        }
+
+        if self.cooldown > 0 {
+            self.cooldown -= 1;
+            return;
+        }
+        if let Some(action) = self.plan.pop_front() {
+            action.apply(grid, end_cb);
+            self.cooldown = self.gap;
+        }
+    }
+
+    /// the inputs for the placement the bot goes for
+    fn choose(&mut self, grid: &Grid) -> Vec<Action> {
+        // the copied randomizer would deal the real pieces past the preview, so don't look beyond it
+        let depth = self.settings.depth.clamp(1, grid.rules.preview as u32 + 1);
+        let mut placements = ranked(options(grid))
+            .into_iter()
+            .take(if depth > 1 { BEAM_WIDTH } else { usize::MAX })
+            .map(|(value, placement)| match depth > 1 {
+                true => (reward(&placement.grid) + search(&placement.grid, depth - 1), placement.actions),
+                false => (value, placement.actions),
+            })
+            .collect::<Vec<(f32, Vec<Action>)>>();
+        placements.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+
+        if placements.len() > 1 && self.rng.gen::<f32>() < self.settings.error_rate {
+            let choice = self.rng.gen_range(1..placements.len().min(MISTAKE_CHOICES + 1));
+            return placements.swap_remove(choice).1;
+        }
+        // with nowhere safe to go, just drop the piece where it is
+        placements.into_iter().next().map_or(vec![Action::HardDrop], |(_, actions)| actions)
+    }
+}
+
+/// the best value reachable from `grid` placing `depth` more pieces
+fn search(grid: &Grid, depth: u32) -> f32 {
+    if depth == 0 || grid.active_shape.is_none() {
+        return evaluate(grid);
+    }
+
+    ranked(placements(grid))
+        .into_iter()
+        .take(BEAM_WIDTH)
+        .map(|(_, placement)| reward(&placement.grid) + search(&placement.grid, depth - 1))
+        .max_by(f32::total_cmp)
+        .unwrap_or(f32::MIN)
+}
+
+/// the placements that don't top out, best first by how they look straight away
+fn ranked(placements: Vec<Placement>) -> Vec<(f32, Placement)> {
+    let mut ranked = placements
+        .into_iter()
+        .filter(|placement| !placement.topped_out)
+        .map(|placement| (reward(&placement.grid) + evaluate(&placement.grid), placement))
+        .collect::<Vec<(f32, Placement)>>();
+    ranked.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+    ranked
+}
+
+/// every placement of the active piece, plus every placement of the piece swapped in by a hold
+fn options(grid: &Grid) -> Vec<Placement> {
+    let mut options = placements(grid);
+
+    if grid.rules.hold && !grid.finesse.held {
+        let mut held = grid.clone();
+        Action::Hold.apply(&mut held, |_| {});
+        if held.active_shape.is_some() {
+            options.extend(placements(&held).into_iter().map(|mut placement| {
+                placement.actions.insert(0, Action::Hold);
+                placement
+            }));
+        }
+    }
+    options
+}
+
+/// every spot the active piece can be hard dropped onto by rotating first and then moving sideways
+fn placements(grid: &Grid) -> Vec<Placement> {
+    let mut placements = Vec::new();
+    let mut seen = HashSet::new();
+
+    let states = grid.active_shape.and_then(|shape| grid.rules.piece(shape)).map_or(1, |piece| piece.rotations.len());
+    for rotations in 0..states {
+        let mut rotated = grid.clone();
+        for _ in 0..rotations {
+            Action::RotateCw.apply(&mut rotated, |_| {});
+        }
+
+        for direction in [Action::Left, Action::Right] {
+            let mut moved = rotated.clone();
+            let mut actions = vec![Action::RotateCw; rotations];
+
+            loop {
+                let mut landing = moved.ghost_blocks();
+                landing.sort();
+                if seen.insert(landing) {
+                    let mut placed = moved.clone();
+                    let mut topped_out = false;
+                    Action::HardDrop.apply(&mut placed, |_| topped_out = true);
+
+                    let mut placement_actions = actions.clone();
+                    placement_actions.push(Action::HardDrop);
+                    placements.push(Placement {
+                        actions: placement_actions,
+                        grid: placed,
+                        topped_out,
+                    });
+                }
+
+                let before = moved.active_positions();
+                direction.apply(&mut moved, |_| {});
+                if moved.active_positions() == before {
+                    break;
+                }
+                actions.push(direction);
+            }
+        }
+    }
+    placements
+}
+
+/// what the last placement earned on its own
+fn reward(grid: &Grid) -> f32 {
+    grid.last_clear.as_ref().map_or(0.0, |clear| {
+        clear.lines as f32 * LINE_WEIGHT + clear.attack as f32 * ATTACK_WEIGHT
+    })
+}
+
+/// how good the shape of the locked stack is, ignoring the active piece
+fn evaluate(grid: &Grid) -> f32 {
+    let mut heights = vec![0; grid.width];
+    let mut holes = 0;
+
+    for (x, height) in heights.iter_mut().enumerate() {
+        let top = (0..grid.height).find(|y| grid.rows[*y].cells[x].is_full());
+        if let Some(top) = top {
+            *height = grid.height - top;
+            holes += (top..grid.height).filter(|y| !grid.rows[*y].cells[x].is_full()).count();
+        }
+    }
+
+    let aggregate = heights.iter().sum::<usize>();
+    let bumpiness = heights.windows(2).map(|pair| pair[0].abs_diff(pair[1])).sum::<usize>();
+
+    aggregate as f32 * HEIGHT_WEIGHT + holes as f32 * HOLE_WEIGHT + bumpiness as f32 * BUMPINESS_WEIGHT
+}
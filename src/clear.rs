@@ -0,0 +1,54 @@
+// src/clear.rs
+
+use crate::shape::Shape;
+
+/// what the last locked piece did, kept on the grid for modes that care about more than the score
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Clear {
+    pub lines: u32,
+    /// indices of the cleared rows, as they were before the rows above fell down
+    pub rows: Vec<usize>,
+    pub t_spin: bool,
+    /// the board was left without any locked blocks
+    pub perfect: bool,
+    pub points: u32,
+    /// the previous line clear was also a tetris or t-spin, so this one earned the back-to-back bonus
+    pub back_to_back: bool,
+    /// how many locks in a row have cleared lines, including this one
+    pub combo: u32,
+    /// lines of garbage this clear sends in versus
+    pub attack: u32,
+}
+
+impl Clear {
+    /// what the clear is called, e.g. "T-SPIN DOUBLE", then its bonuses and the points it earned.
+    /// `shape` is the piece that made it, other spin pieces get their own letter in front of "-SPIN"
+    pub fn labels(&self, shape: Shape) -> Vec<String> {
+        let lines = match self.lines {
+            0 => None,
+            1 => Some("SINGLE".to_string()),
+            2 => Some("DOUBLE".to_string()),
+            3 => Some("TRIPLE".to_string()),
+            4 => Some("TETRIS".to_string()),
+            lines => Some(format!("{} LINES", lines)),
+        };
+        let mut labels = vec![match (self.t_spin, lines) {
+            (true, Some(lines)) => format!("{}-SPIN {}", shape.to_char(), lines),
+            (true, None) => format!("{}-SPIN", shape.to_char()),
+            (false, lines) => lines.unwrap_or_default(),
+        }];
+        if self.back_to_back {
+            labels.push("BACK-TO-BACK".to_string());
+        }
+        if self.combo > 1 {
+            labels.push(format!("{} COMBO", self.combo - 1));
+        }
+        if self.perfect {
+            labels.push("PERFECT CLEAR".to_string());
+        }
+        if self.points > 0 {
+            labels.push(format!("+{}", self.points));
+        }
+        labels
+    }
+}
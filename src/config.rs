@@ -1,9 +1,15 @@
 use crossterm::event::{self, Event, KeyCode, KeyEventKind};
 use crossterm::terminal;
-use serde::de::{self, Deserializer, Visitor};
+use serde::de::{self, Deserializer};
 use serde::{Deserialize, Serialize, Serializer};
 use std::io::Write;
-use std::{fmt, io};
+use std::io;
+use std::path::PathBuf;
+
+use crate::bot::Difficulty;
+use crate::mode::{Mode, Visibility};
+use crate::stats::{default_stats, Stat};
+use crate::theme::Theme;
 
 #[derive(Clone)]
 pub struct KeyCodeWrapper {
@@ -129,6 +135,19 @@ impl<'de> Deserialize<'de> for KeyCodeWrapper {
     }
 }
 
+/// how a key is written in the help panel and the menu
+pub fn key_name(code: KeyCode) -> String {
+    match code {
+        KeyCode::Char(' ') => "Space".to_string(),
+        KeyCode::Char(c) => c.to_string(),
+        KeyCode::F(n) => format!("F{}", n),
+        KeyCode::PageUp => "Page Up".to_string(),
+        KeyCode::PageDown => "Page Down".to_string(),
+        KeyCode::BackTab => "Shift+Tab".to_string(),
+        code => format!("{:?}", code),
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Config {
     pub move_left: KeyCodeWrapper,
@@ -142,6 +161,180 @@ pub struct Config {
     pub quit: KeyCodeWrapper,
 
     pub soft_drop_ms_per_cell: u8,
+
+    /// movement keys for the second player in local versus, pause and quit are shared
+    #[serde(default)]
+    pub player_two: PlayerKeys,
+
+    /// what was last picked in the main menu, so it's all still there next time
+    #[serde(default)]
+    pub menu: MenuSettings,
+
+    #[serde(default)]
+    pub theme: Theme,
+
+    /// leaves the key bindings out of the game screen
+    #[serde(default)]
+    pub hide_help: bool,
+
+    /// what the stats panel shows, in order
+    #[serde(default = "default_stats")]
+    pub stats: Vec<Stat>,
+
+    /// leaves the stats panel out of the game screen
+    #[serde(default)]
+    pub hide_stats: bool,
+
+    /// draws line clears, locks and hard drops without animating them
+    #[serde(default)]
+    pub no_animations: bool,
+}
+
+/// everything that sets up a single player game
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct GameSettings {
+    pub mode: Mode,
+    /// a rule set file, which makes it a custom game instead of `mode`
+    pub rules: Option<PathBuf>,
+    /// a piece set file to play with instead of the tetrominoes
+    pub pieces: Option<PathBuf>,
+    pub level: u32,
+    pub visibility: Visibility,
+    pub big: bool,
+}
+
+impl Default for GameSettings {
+    fn default() -> Self {
+        GameSettings {
+            mode: Mode::Marathon,
+            rules: None,
+            pieces: None,
+            level: 1,
+            visibility: Visibility::Visible,
+            big: false,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct MenuSettings {
+    pub game: GameSettings,
+    /// the entry of the play menu that was started last
+    pub last_played: String,
+    pub difficulty: Difficulty,
+    /// how many bots there are in battle royale
+    pub bots: usize,
+}
+
+impl Default for MenuSettings {
+    fn default() -> Self {
+        MenuSettings {
+            game: GameSettings::default(),
+            last_played: String::new(),
+            difficulty: Difficulty::Medium,
+            bots: 15,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PlayerKeys {
+    pub move_left: KeyCodeWrapper,
+    pub move_right: KeyCodeWrapper,
+    pub hard_drop: KeyCodeWrapper,
+    pub soft_drop: KeyCodeWrapper,
+    pub rotate_cw: KeyCodeWrapper,
+    pub rotate_ccw: KeyCodeWrapper,
+    pub hold: KeyCodeWrapper,
+}
+
+impl Default for PlayerKeys {
+    fn default() -> Self {
+        let key = |c| KeyCodeWrapper { code: KeyCode::Char(c) };
+        PlayerKeys {
+            move_left: key('a'),
+            move_right: key('d'),
+            hard_drop: key('w'),
+            soft_drop: key('s'),
+            rotate_cw: key('e'),
+            rotate_ccw: key('r'),
+            hold: key('f'),
+        }
+    }
+}
+
+impl Config {
+    /// whether the stats panel shows finesse faults, pieces aren't judged outside finesse mode otherwise
+    pub fn shows_faults(&self) -> bool {
+        !self.hide_stats && self.stats.contains(&Stat::Faults)
+    }
+
+    /// player one's keys are all different, so no binding hides another
+    pub fn check_keys(&self) -> Result<(), String> {
+        check_clashes(&self.player_one_keys())
+    }
+
+    /// player two's keys can't be used for anything else either, a local versus game would give
+    /// them to player one
+    pub fn check_versus_keys(&self) -> Result<(), String> {
+        let two = &self.player_two;
+        let mut keys = self.player_one_keys();
+        keys.extend([
+            ("player_two.move_left", &two.move_left),
+            ("player_two.move_right", &two.move_right),
+            ("player_two.hard_drop", &two.hard_drop),
+            ("player_two.soft_drop", &two.soft_drop),
+            ("player_two.rotate_cw", &two.rotate_cw),
+            ("player_two.rotate_ccw", &two.rotate_ccw),
+            ("player_two.hold", &two.hold),
+        ]);
+        check_clashes(&keys)
+    }
+
+    fn player_one_keys(&self) -> Vec<(&'static str, &KeyCodeWrapper)> {
+        vec![
+            ("move_left", &self.move_left),
+            ("move_right", &self.move_right),
+            ("hard_drop", &self.hard_drop),
+            ("soft_drop", &self.soft_drop),
+            ("rotate_cw", &self.rotate_cw),
+            ("rotate_ccw", &self.rotate_ccw),
+            ("hold", &self.hold),
+            ("pause", &self.pause),
+            ("quit", &self.quit),
+        ]
+    }
+
+    /// these settings without the help panel, for boards that aren't played with these keys
+    pub fn without_help(&self) -> Config {
+        Config { hide_help: true, ..self.clone() }
+    }
+
+    /// these bindings with the movement keys swapped for the second player's
+    pub fn for_player_two(&self) -> Config {
+        let keys = self.player_two.clone();
+        Config {
+            move_left: keys.move_left,
+            move_right: keys.move_right,
+            hard_drop: keys.hard_drop,
+            soft_drop: keys.soft_drop,
+            rotate_cw: keys.rotate_cw,
+            rotate_ccw: keys.rotate_ccw,
+            hold: keys.hold,
+            ..self.clone()
+        }
+    }
+}
+
+fn check_clashes(keys: &[(&str, &KeyCodeWrapper)]) -> Result<(), String> {
+    for (i, (name, key)) in keys.iter().enumerate() {
+        if let Some((other, _)) = keys[..i].iter().find(|(_, other)| other.code == key.code) {
+            return Err(format!("{} is bound to the same key as {}", name, other));
+        }
+    }
+    Ok(())
 }
 
 fn input(prompt: String) -> KeyCodeWrapper {
@@ -168,16 +361,27 @@ fn input(prompt: String) -> KeyCodeWrapper {
 }
 
 pub fn interactive_config() -> Config {
-    let move_left = input("Press the key you want to use for moving left".to_string());
-    let move_right = input("Press the key you want to use for moving right".to_string());
-    let hard_drop = input("Press the key you want to use for hard dropping".to_string());
-    let soft_drop = input("Press the key you want to use for soft dropping".to_string());
-    let rotate_cw = input("Press the key you want to use for rotating clockwise".to_string());
-    let rotate_ccw =
-        input("Press the key you want to use for rotating counter-clockwise".to_string());
-    let hold = input("Press the key you want to use for holding".to_string());
-    let pause = input("Press the key you want to use for pausing".to_string());
-    let quit = input("Press the key you want to use for quitting".to_string());
+    // every key asked for has to be different from the ones before it
+    let mut taken = Vec::new();
+    let mut key = |action: &str| loop {
+        let key = input(format!("Press the key you want to use for {}", action));
+        if !taken.contains(&key.code) {
+            taken.push(key.code);
+            break key;
+        }
+        terminal::disable_raw_mode().unwrap();
+        println!("That key is already bound, please press another one");
+        terminal::enable_raw_mode().unwrap();
+    };
+    let move_left = key("moving left");
+    let move_right = key("moving right");
+    let hard_drop = key("hard dropping");
+    let soft_drop = key("soft dropping");
+    let rotate_cw = key("rotating clockwise");
+    let rotate_ccw = key("rotating counter-clockwise");
+    let hold = key("holding");
+    let pause = key("pausing");
+    let quit = key("quitting");
 
     terminal::disable_raw_mode().unwrap();
     let soft_drop_ms_per_cell = loop {
@@ -205,5 +409,12 @@ pub fn interactive_config() -> Config {
         pause,
         quit,
         soft_drop_ms_per_cell,
+        player_two: PlayerKeys::default(),
+        menu: MenuSettings::default(),
+        theme: Theme::default(),
+        hide_help: false,
+        stats: default_stats(),
+        hide_stats: false,
+        no_animations: false,
     }
 }
@@ -0,0 +1,241 @@
+// src/drill.rs
+//
+// Drills preset a board and queue like puzzles, but instead of a goal they have a target: the
+// rows of the finished setup, with every cell marked by the letter of the piece that belongs there.
+// Each locked piece has to land on cells marked with its own letter, otherwise the drill shows a
+// hint and starts over. The built-in drills live in `drills/` and follow the same format, e.g.
+//
+//     {
+//         "name": "T-spin double",
+//         "description": "Hang the O over the slot, then spin the T in",
+//         "rows": ["##...#####", "###.######"],
+//         "queue": ["O", "T"],
+//         "target": ["....OO....", "....OO....", "##TTT#####", "###T######"],
+//         "hints": ["The O rests on the right edge of the slot", "Rotate the T into the slot"]
+//     }
+
+use std::cell::Cell;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use crossterm::event::{self, KeyCode};
+use ratatui::text::Line;
+use serde::Deserialize;
+
+use crate::config::Config;
+use crate::grid::Grid;
+use crate::menu::Paused;
+use crate::mode::Mode;
+use crate::puzzle::{json_files, parse_rows, preset_grid};
+use crate::shape::Shape;
+use crate::speed::FRAMES_PER_SECOND;
+use crate::ui;
+use crate::utils::{handle_events, pause, restarting, wait_for_key, wait_for_room, Round};
+
+const BUILT_IN: [&str; 4] = [
+    include_str!("../drills/01-tsd-right.json"),
+    include_str!("../drills/02-tsd-left.json"),
+    include_str!("../drills/03-two-line-pc.json"),
+    include_str!("../drills/04-tsd-stack.json"),
+];
+
+#[derive(Deserialize, Clone)]
+pub struct Drill {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub rows: Vec<String>,
+    pub queue: Vec<Shape>,
+    #[serde(default)]
+    pub hold: Option<Shape>,
+    pub target: Vec<String>,
+    /// one per placement, the last one is reused for any placements after it
+    #[serde(default)]
+    pub hints: Vec<String>,
+}
+
+enum Outcome {
+    Complete,
+    /// the placement at this index didn't match the target
+    Mistake(usize),
+    ToppedOut,
+}
+
+impl Drill {
+    pub fn built_in() -> Vec<Drill> {
+        BUILT_IN
+            .iter()
+            .map(|drill| serde_json::from_str(drill).expect("built-in drills are valid"))
+            .collect()
+    }
+
+    pub fn load_all(path: &Path) -> Result<Vec<Drill>, String> {
+        json_files(path)?
+            .iter()
+            .map(|path| {
+                let file = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+                serde_json::from_str(&file).map_err(|e| format!("{}: {}", path.display(), e))
+            })
+            .collect()
+    }
+
+    fn hint(&self, placement: usize) -> &str {
+        self.hints
+            .get(placement)
+            .or(self.hints.last())
+            .map_or("Check the target shape and try again", |hint| hint.as_str())
+    }
+
+    /// the target as a board sized grid of which shape belongs in each cell
+    fn target(&self, grid: &Grid) -> Result<Vec<Vec<Option<Shape>>>, String> {
+        let mut target = vec![vec![None; grid.width]; grid.height];
+        for (x, y, c) in parse_rows(&self.name, &self.target, grid.width, grid.height)? {
+            target[y][x] = Shape::from_char(c);
+        }
+        Ok(target)
+    }
+}
+
+/// plays the drills in order, a drill is repeated until it's completed or skipped
+pub fn play(terminal: &mut ratatui::DefaultTerminal, drills: Vec<Drill>, mut cfg: Config, config_file: &str) -> Result<(), String> {
+    let mut index = 0;
+
+    while index < drills.len() {
+        let drill = &drills[index];
+        let Some((grid, outcome)) = restarting(|| play_one(terminal, drill, &mut cfg, config_file))? else {
+            return Ok(());
+        };
+
+        let mut message = vec![Line::from(drill.name.clone())];
+        match outcome {
+            Outcome::Complete => message.push(Line::from("Drill complete!")),
+            Outcome::Mistake(placement) => {
+                message.push(Line::from("Not quite"));
+                message.push(Line::from(""));
+                message.push(Line::from(format!("Hint: {}", drill.hint(placement))));
+            }
+            Outcome::ToppedOut => message.push(Line::from("Topped out")),
+        }
+        message.push(Line::from(""));
+        let complete = matches!(outcome, Outcome::Complete);
+        if !complete {
+            message.push(Line::from("n: skip drill"));
+        }
+        message.push(Line::from(match (complete, index + 1 < drills.len()) {
+            (true, true) => "any key: next drill",
+            (true, false) => "any key: finish",
+            (false, _) => "any key: try again",
+        }));
+
+        terminal
+            .draw(|frame| {
+                ui::draw(frame, grid.clone(), &cfg);
+                ui::draw_message(frame, message.clone());
+            })
+            .map_err(|e| e.to_string())?;
+
+        match wait_for_key()? {
+            code if code == cfg.quit.code => break,
+            KeyCode::Char('n') if !complete => index += 1,
+            _ if complete => index += 1,
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// returns the final grid and how the drill went, or `None` if the quit key was pressed
+fn play_one(terminal: &mut ratatui::DefaultTerminal, drill: &Drill, cfg: &mut Config, config_file: &str) -> Result<Round<Option<(Grid, Outcome)>>, String> {
+    let mut grid = preset_grid(&drill.name, &drill.rows, &drill.queue, drill.hold)?;
+    grid.mode = Mode::Drill;
+    grid.finesse.judged = cfg.shows_faults();
+    let mut target = drill.target(&grid)?;
+    let topped_out = Cell::new(false);
+    let frame_time = Duration::from_secs(1) / FRAMES_PER_SECOND;
+    let mut last_frame = Instant::now();
+    let mut checked = 0;
+
+    grid.next(None);
+
+    loop {
+        let mut notes = vec![Line::from(drill.name.clone())];
+        notes.extend(textwrap(&drill.description, 20).into_iter().map(Line::from));
+        notes.push(Line::from(format!("Piece {}/{}", checked + 1, drill.queue.len())));
+
+        if wait_for_room(terminal, cfg, ui::min_size(&grid, &cfg.theme), &mut last_frame)? {
+            return Ok(Round::Over(None));
+        }
+        terminal
+            .draw(|frame| {
+                ui::draw(frame, grid.clone(), cfg);
+                ui::draw_notes(frame, &grid, cfg, notes);
+            })
+            .map_err(|e| e.to_string())?;
+
+        while last_frame.elapsed() >= frame_time {
+            last_frame += frame_time;
+            grid.tick(|_| topped_out.set(true));
+        }
+
+        while event::poll(Duration::from_millis(1)).unwrap() {
+            if handle_events(&mut grid, |_| topped_out.set(true), cfg, terminal, &mut Vec::new())? {
+                return Ok(Round::Over(None));
+            }
+        }
+
+        if grid.paused {
+            match pause(terminal, &mut grid, cfg, config_file, &mut last_frame)? {
+                Paused::Resume => {}
+                Paused::Restart => return Ok(Round::Restarted),
+                Paused::Quit => return Ok(Round::Over(None)),
+            }
+        }
+
+        if topped_out.get() {
+            return Ok(Round::Over(Some((grid, Outcome::ToppedOut))));
+        }
+
+        if grid.pieces_locked as usize > checked {
+            if !grid.last_lock.iter().all(|(x, y, shape)| target[*y][*x] == Some(*shape)) {
+                return Ok(Round::Over(Some((grid, Outcome::Mistake(checked)))));
+            }
+            checked += 1;
+
+            // keep the target lined up with the board when rows are cleared
+            if let Some(clear) = &grid.last_clear {
+                for row in &clear.rows {
+                    target.remove(*row);
+                    target.insert(0, vec![None; grid.width]);
+                }
+            }
+        }
+
+        let filled = target.iter().zip(&grid.rows).all(|(target, row)| {
+            target.iter().zip(&row.cells).all(|(target, cell)| target.is_none() || cell.is_full())
+        });
+        if filled {
+            return Ok(Round::Over(Some((grid, Outcome::Complete))));
+        }
+        // a piece left in the hold can't be placed any more
+        if grid.active_shape.is_none() && grid.next_shapes.is_empty() {
+            return Ok(Round::Over(Some((grid, Outcome::Mistake(checked)))));
+        }
+    }
+}
+
+/// splits `text` into lines of at most `width` characters, breaking between words
+fn textwrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for word in text.split_whitespace() {
+        match lines.last_mut() {
+            Some(line) if line.len() + 1 + word.len() <= width => {
+                line.push(' ');
+                line.push_str(word);
+            }
+            _ => lines.push(word.to_string()),
+        }
+    }
+    lines
+}
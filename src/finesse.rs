@@ -0,0 +1,308 @@
+// src/finesse.rs
+//
+// Finesse is placing every piece with as few key presses as possible. When a piece locks, the
+// shortest sequence of moves and rotations from its spawn position to where it was hard dropped is
+// worked out and compared with what was actually pressed for it, with a direction held until the
+// piece stops counting as one input. The search steps through the piece's positions on the board
+// rather than playing inputs on copies of it, and only runs in finesse mode or while the stats
+// panel shows faults.
+
+use std::collections::{HashSet, VecDeque};
+use std::fmt;
+
+use crate::grid::Grid;
+use crate::mode::Mode;
+use crate::shape::{Piece, Shape};
+
+/// longest input sequence the search looks at before giving up on a placement
+const MAX_INPUTS: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Input {
+    Left,
+    Right,
+    RotateCw,
+    RotateCcw,
+    /// holding a direction until the piece is stopped by a wall or the stack
+    DasLeft,
+    DasRight,
+}
+
+impl Input {
+    const ALL: [Input; 6] = [Input::Left, Input::Right, Input::RotateCw, Input::RotateCcw, Input::DasLeft, Input::DasRight];
+
+    /// where the input takes a piece at `from`, or `None` if it's blocked
+    fn apply(&self, grid: &Grid, piece: &Piece, from: Position) -> Option<Position> {
+        let to = match self {
+            Input::Left => from.moved(-1, 0),
+            Input::Right => from.moved(1, 0),
+            Input::RotateCw => return from.turned(grid, piece, true),
+            Input::RotateCcw => return from.turned(grid, piece, false),
+            Input::DasLeft => return from.slid(grid, piece, -1),
+            Input::DasRight => return from.slid(grid, piece, 1),
+        };
+        to.fits(grid, piece).then_some(to)
+    }
+}
+
+/// a piece during the search, by the point it turns around and its rotation state. kicks can move
+/// it up or down as well as sideways, so the row is part of it too
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Position {
+    x: i32,
+    y: i32,
+    rotation: usize,
+}
+
+impl Position {
+    fn moved(self, dx: i32, dy: i32) -> Position {
+        Position { x: self.x + dx, y: self.y + dy, ..self }
+    }
+
+    /// moves the piece sideways as far as it goes, or `None` if it can't move at all
+    fn slid(self, grid: &Grid, piece: &Piece, dx: i32) -> Option<Position> {
+        let mut to = self;
+        while to.moved(dx, 0).fits(grid, piece) {
+            to = to.moved(dx, 0);
+        }
+        (to != self).then_some(to)
+    }
+
+    /// turns the piece, trying its kicks if it doesn't fit on the spot, the same way the grid does
+    fn turned(self, grid: &Grid, piece: &Piece, clockwise: bool) -> Option<Position> {
+        if piece.rotations.len() < 2 {
+            return None;
+        }
+        let (rotation, kicks) = piece.turn(self.rotation, clockwise);
+        std::iter::once((0, 0))
+            .chain(kicks)
+            .map(|(dx, dy)| Position { rotation, ..self.moved(dx, dy) })
+            .find(|to| to.fits(grid, piece))
+    }
+
+    fn cells(self, piece: &Piece) -> impl Iterator<Item = (i32, i32)> + '_ {
+        piece.rotations[self.rotation].iter().map(move |(cx, cy)| (self.x + cx, self.y + cy))
+    }
+
+    /// whether the piece is on the board without overlapping any locked blocks, the active piece
+    /// is ignored
+    fn fits(self, grid: &Grid, piece: &Piece) -> bool {
+        self.cells(piece).all(|(x, y)| {
+            x >= 0 && y >= 0 && x < grid.width as i32 && y < grid.height as i32 && !grid.rows[y as usize].cells[x as usize].is_full()
+        })
+    }
+
+    /// the sorted cells the piece would lock on if it was hard dropped from here
+    fn landing(self, grid: &Grid, piece: &Piece) -> Vec<(usize, usize)> {
+        let mut landing = self;
+        while landing.moved(0, 1).fits(grid, piece) {
+            landing = landing.moved(0, 1);
+        }
+        let mut cells = landing.cells(piece).map(|(x, y)| (x as usize, y as usize)).collect::<Vec<(usize, usize)>>();
+        cells.sort();
+        cells
+    }
+}
+
+impl fmt::Display for Input {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Input::Left => "left",
+            Input::Right => "right",
+            Input::RotateCw => "cw",
+            Input::RotateCcw => "ccw",
+            Input::DasLeft => "das left",
+            Input::DasRight => "das right",
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Fault {
+    pub pressed: Vec<Input>,
+    pub optimal: Vec<Input>,
+    /// which judged piece this was, so a fault can be flagged while it's still the latest one
+    pub piece: u32,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Finesse {
+    /// inputs pressed for the active piece so far
+    pub inputs: Vec<Input>,
+    /// the active piece came out of the hold, so its inputs can't be judged
+    pub held: bool,
+    /// pieces are judged in finesse mode, and otherwise only while the faults are on show
+    pub judged: bool,
+    /// pieces that could be judged, placements that need a soft drop are skipped
+    pub pieces: u32,
+    pub faults: u32,
+    pub last_fault: Option<Fault>,
+}
+
+impl Finesse {
+    pub fn percentage(&self) -> f32 {
+        if self.pieces == 0 {
+            return 100.0;
+        }
+        (self.pieces - self.faults) as f32 / self.pieces as f32 * 100.0
+    }
+
+    /// whether the most recently placed piece was a fault
+    pub fn just_faulted(&self) -> bool {
+        self.last_fault.as_ref().is_some_and(|fault| fault.piece == self.pieces)
+    }
+}
+
+/// the shortest inputs that take `shape` from its spawn position to a spot where a hard drop lands it on `target`
+pub fn optimal_inputs(grid: &Grid, shape: Shape, target: &[(usize, usize)]) -> Option<Vec<Input>> {
+    let mut target = target.to_vec();
+    target.sort();
+
+    // spawned the way `Grid::spawn` does it, nudged down a row if there's room
+    let piece = grid.rules.piece(shape)?;
+    let spawn = Position {
+        x: (grid.width / 2) as i32 + piece.spawn.0,
+        y: piece.spawn.1,
+        rotation: 0,
+    };
+    if !spawn.fits(grid, piece) {
+        return None;
+    }
+    let start = if spawn.moved(0, 1).fits(grid, piece) { spawn.moved(0, 1) } else { spawn };
+
+    let mut seen = HashSet::from([start]);
+    let mut queue = VecDeque::from([(start, Vec::new())]);
+
+    while let Some((position, inputs)) = queue.pop_front() {
+        if position.landing(grid, piece) == target {
+            return Some(inputs);
+        }
+        if inputs.len() >= MAX_INPUTS {
+            continue;
+        }
+
+        for input in Input::ALL {
+            let Some(next) = input.apply(grid, piece, position) else {
+                continue;
+            };
+            if seen.insert(next) {
+                let mut next_inputs = inputs.clone();
+                next_inputs.push(input);
+                queue.push_back((next, next_inputs));
+            }
+        }
+    }
+
+    None
+}
+
+impl Grid {
+    /// moves the active piece a column and records the press. held keys repeat as presses, so a run
+    /// of them that leaves the piece against a wall or the stack is recorded as one DAS input
+    pub fn shift_active(&mut self, dx: i32) {
+        let (tap, das) = if dx < 0 { (Input::Left, Input::DasLeft) } else { (Input::Right, Input::DasRight) };
+        let before = self.active_positions();
+        self.move_active_blocks(dx, 0);
+        let moved = self.active_positions() != before;
+        if !moved && self.finesse.inputs.last() == Some(&das) {
+            return;
+        }
+
+        self.finesse.inputs.push(tap);
+        let blocked = self.active_positions().iter().any(|&(x, y)| {
+            let x = x as i32 + dx;
+            x < 0 || x >= self.width as i32 || self.rows[y].cells[x as usize].is_full()
+        });
+        let taps = self.finesse.inputs.iter().rev().take_while(|input| **input == tap).count();
+        if blocked && taps > 1 {
+            let kept = self.finesse.inputs.len() - taps;
+            self.finesse.inputs.truncate(kept);
+            self.finesse.inputs.push(das);
+        }
+    }
+
+    /// called just before the active piece locks
+    pub(crate) fn judge_finesse(&mut self) {
+        if self.mode != Mode::Finesse && !self.finesse.judged {
+            return;
+        }
+        let (Some(shape), false) = (self.active_shape, self.finesse.held) else {
+            return;
+        };
+
+        let target = self.active_positions();
+        let Some(optimal) = optimal_inputs(self, shape, &target) else {
+            return;
+        };
+
+        self.finesse.pieces += 1;
+        if self.finesse.inputs.len() > optimal.len() {
+            self.finesse.faults += 1;
+            self.finesse.last_fault = Some(Fault {
+                pressed: self.finesse.inputs.clone(),
+                optimal,
+                piece: self.finesse.pieces,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// spawns `shape`, plays `inputs` on the grid itself, and searches for where it would land
+    fn search(shape: char, inputs: &[Input]) -> Option<Vec<Input>> {
+        let shape = Shape::from_char(shape).unwrap();
+        let mut grid = Grid::new();
+        assert!(grid.spawn(shape));
+        for input in inputs {
+            match input {
+                Input::Left | Input::DasLeft => grid.shift_active(-1),
+                Input::Right | Input::DasRight => grid.shift_active(1),
+                Input::RotateCw => grid.rotate_active_block(),
+                Input::RotateCcw => grid.rotate_active_block_ccw(),
+            }
+            if matches!(input, Input::DasLeft | Input::DasRight) {
+                let dx = if *input == Input::DasLeft { -1 } else { 1 };
+                for _ in 0..grid.width {
+                    grid.shift_active(dx);
+                }
+            }
+        }
+        optimal_inputs(&grid, shape, &grid.ghost_blocks())
+    }
+
+    #[test]
+    fn dropping_where_it_spawns_takes_nothing() {
+        assert_eq!(search('T', &[]), Some(Vec::new()));
+    }
+
+    #[test]
+    fn finds_the_shortest_way() {
+        assert_eq!(search('T', &[Input::Left]), Some(vec![Input::Left]));
+        assert_eq!(search('T', &[Input::RotateCw, Input::RotateCw, Input::RotateCw]), Some(vec![Input::RotateCcw]));
+        assert_eq!(search('O', &[Input::Left, Input::Right, Input::Right]), Some(vec![Input::Right]));
+    }
+
+    #[test]
+    fn walls_stop_the_piece() {
+        assert_eq!(search('I', &[Input::Left; 8]), Some(vec![Input::DasLeft]));
+        assert_eq!(search('T', &[Input::RotateCw, Input::DasRight]), Some(vec![Input::RotateCw, Input::DasRight]));
+    }
+
+    #[test]
+    fn holding_into_the_wall_is_one_input() {
+        let mut grid = Grid::new();
+        grid.finesse.judged = true;
+        assert!(grid.spawn(Shape::from_char('L').unwrap()));
+        for _ in 0..8 {
+            grid.shift_active(-1);
+        }
+        assert_eq!(grid.finesse.inputs, vec![Input::DasLeft]);
+
+        while grid.bring_down(None, |_| {}) {}
+        grid.judge_finesse();
+        assert_eq!((grid.finesse.pieces, grid.finesse.faults), (1, 0));
+    }
+}
@@ -0,0 +1,155 @@
+// src/garbage.rs
+
+use std::collections::VecDeque;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::block::{Block, Row};
+use crate::grid::Grid;
+
+/// garbage waiting to be added to the board or sent to an opponent, and where its holes go
+#[derive(Clone)]
+pub struct Garbage {
+    rng: StdRng,
+    /// column of the hole in the last garbage row
+    hole: usize,
+    /// batches of lines sent by opponents, added to the board once a piece locks without clearing
+    pub incoming: VecDeque<u32>,
+    /// lines waiting to be picked up and passed on to an opponent
+    pub outgoing: u32,
+    /// lines of attack made this game, including any spent cancelling incoming garbage
+    pub attack: u32,
+}
+
+impl Garbage {
+    pub fn new(seed: u64, width: usize) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let hole = rng.gen_range(0..width);
+        Garbage {
+            rng,
+            hole,
+            incoming: VecDeque::new(),
+            outgoing: 0,
+            attack: 0,
+        }
+    }
+
+    /// total lines of incoming garbage, shown as the warning meter
+    pub fn pending(&self) -> u32 {
+        self.incoming.iter().sum()
+    }
+
+    /// uses `lines` of attack to cancel incoming garbage first, whatever is left is queued to be sent
+    pub fn send(&mut self, mut lines: u32) {
+        self.attack += lines;
+        while let Some(batch) = self.incoming.front_mut() {
+            if lines == 0 {
+                break;
+            }
+            let cancelled = lines.min(*batch);
+            *batch -= cancelled;
+            lines -= cancelled;
+            if *batch == 0 {
+                self.incoming.pop_front();
+            }
+        }
+        self.outgoing += lines;
+    }
+
+    fn next_hole(&mut self, width: usize, messiness: f32) -> usize {
+        if self.rng.gen::<f32>() < messiness {
+            self.hole = self.rng.gen_range(0..width);
+        }
+        self.hole
+    }
+}
+
+impl Grid {
+    /// adds all incoming garbage to the board, every batch gets a hole of its own.
+    /// returns false if blocks were pushed off the top of the board
+    pub(crate) fn receive_garbage(&mut self) -> bool {
+        let mut fits = true;
+        while let Some(lines) = self.garbage.incoming.pop_front() {
+            self.garbage.hole = self.garbage.rng.gen_range(0..self.width);
+            fits &= self.add_garbage(lines, 0.0);
+        }
+        fits
+    }
+
+    /// pushes the stack up by `lines` rows of garbage and lifts the active piece out of the way if it has to.
+    /// returns false if blocks were pushed off the top of the board
+    pub fn add_garbage(&mut self, lines: u32, messiness: f32) -> bool {
+        let active = self.active_positions();
+        for &(x, y) in &active {
+            self.rows[y].cells[x] = Block::Empty;
+        }
+
+        let mut fits = true;
+        for _ in 0..lines {
+            let hole = self.garbage.next_hole(self.width, messiness);
+            let top = self.rows.remove(0);
+            fits &= !top.cells.iter().any(|cell| cell.is_full());
+
+            let mut row = Row::empty(self.width);
+            for x in (0..self.width).filter(|x| *x != hole) {
+                row.cells[x] = Block::Garbage;
+                row.locked_at[x] = self.frames;
+            }
+            self.rows.push(row);
+        }
+
+        let Some(shape) = self.active_shape.filter(|_| !active.is_empty()) else {
+            return fits;
+        };
+        let lift = (0..=self.height).find(|lift| {
+            active
+                .iter()
+                .all(|&(x, y)| y >= *lift && !self.rows[y - lift].cells[x].is_full())
+        });
+        match lift {
+            Some(lift) => {
+                for &(x, y) in &active {
+                    self.rows[y - lift].cells[x] = Block::Active(shape);
+                }
+                self.lowest_row = self.lowest_row.saturating_sub(lift);
+                fits
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn send_cancels_incoming_garbage_first() {
+        let mut garbage = Garbage::new(1, 10);
+        garbage.incoming.extend([2, 3]);
+
+        garbage.send(1);
+        assert_eq!(garbage.incoming, [1, 3]);
+        assert_eq!(garbage.outgoing, 0);
+
+        // the first batch is used up and the rest goes into the second
+        garbage.send(3);
+        assert_eq!(garbage.incoming, [1]);
+        assert_eq!(garbage.outgoing, 0);
+
+        garbage.send(4);
+        assert!(garbage.incoming.is_empty());
+        assert_eq!(garbage.outgoing, 3);
+        assert_eq!(garbage.attack, 8);
+    }
+
+    #[test]
+    fn send_without_incoming_garbage_goes_straight_out() {
+        let mut garbage = Garbage::new(1, 10);
+        garbage.send(0);
+        garbage.send(2);
+        assert_eq!(garbage.outgoing, 2);
+        assert_eq!(garbage.pending(), 0);
+    }
+}
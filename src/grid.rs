@@ -1,83 +1,250 @@
+use std::collections::VecDeque;
+
+use crate::animation::Animations;
 use crate::block::{Block, Row};
+use crate::clear::Clear;
+use crate::finesse::Finesse;
+use crate::garbage::Garbage;
 use crate::shape::Shape;
-use crate::constants::{ROW_HEIGHT, ROW_WIDTH};
+use crate::master::{self, MasterState};
+use crate::mode::{Mode, Visibility, REVEAL_FRAMES, ZEN_CLEAR_ROWS};
+use crate::randomizer::Randomizer;
+use crate::rules::RuleSet;
+use crate::stats::Stats;
+use crate::speed::{Speed, FRAMES_PER_SECOND, GRAVITY_UNIT, NO_LOCK};
+use crate::versus;
+
+/// rows at the top of the screen that end the game when a block locks in them
+const TOP_OUT_ROWS: usize = 2;
 
 #[derive(Clone)]
 pub struct Grid {
-    pub rows: [Row; ROW_HEIGHT],
+    pub rows: Vec<Row>,
+    pub width: usize,
+    pub height: usize,
+    /// how many board cells each cell of `rows` covers along each axis, 2 in big mode
+    pub scale: usize,
     pub active_shape: Option<Shape>,
-    pub next_shape: Shape,
+    /// which of the active piece's rotation states it's in
+    pub active_rotation: usize,
+    /// upcoming pieces, the front one is shown as the next shape
+    pub next_shapes: VecDeque<Shape>,
+    /// only take pieces from `next_shapes` instead of topping it up with random ones
+    pub fixed_queue: bool,
     pub held_shape: Option<Shape>,
     pub shapes: u32,
     pub score: u32,
     pub level: u32,
+    pub lines: u32,
+    pub last_clear: Option<Clear>,
+    /// locks in a row that have cleared lines
+    pub combo: u32,
+    /// whether the last line clear was a tetris or t-spin
+    pub back_to_back: bool,
+    pub pieces_locked: u32,
+    /// where the last piece locked, before any rows were cleared
+    pub last_lock: Vec<(usize, usize, Shape)>,
+    /// whether the last thing to move the active piece was a rotation, needed to detect t-spins
+    pub last_move_rotation: bool,
     pub paused: bool,
+    /// the board column last clicked for the active piece, clicking it again drops the piece there
+    pub clicked_column: Option<usize>,
+    pub mode: Mode,
+    pub visibility: Visibility,
+    /// locked blocks are shown regardless of `visibility` until this frame
+    pub reveal_until: u64,
+    /// frames the active piece has been resting on the stack, if it has landed
+    pub lock_timer: Option<u32>,
+    /// lowest row the active piece has reached, moving below it resets the lock timer
+    pub lowest_row: usize,
+    pub gravity_acc: u32,
+    /// frames left until the next piece spawns
+    pub spawn_delay: u32,
+    pub frames: u64,
+    pub soft_drop_cells: u32,
+    pub master: MasterState,
+    pub finesse: Finesse,
+    pub stats: Stats,
+    pub animations: Animations,
+    pub rules: RuleSet,
+    pub randomizer: Randomizer,
+    pub garbage: Garbage,
 }
 
 impl Grid {
     pub fn new() -> Self {
+        Self::with_rules(RuleSet::default(), 1)
+    }
+
+    /// a board made of `scale`×`scale` cells, the logical board shrinks to match
+    pub fn with_rules(rules: RuleSet, scale: usize) -> Self {
+        let width = rules.width / scale;
+        let height = rules.height / scale;
+        let seed = rules.seed.unwrap_or_else(rand::random);
+        let mut randomizer = Randomizer::new(rules.randomizer, rules.shapes(), seed);
+        let next_shapes = (0..rules.preview.max(1)).map(|_| randomizer.next()).collect();
+
         Grid {
-            rows: [Row {
-                cells: [Block::Empty; ROW_WIDTH],
-            }; ROW_HEIGHT],
+            rows: vec![Row::empty(width); height],
+            width,
+            height,
+            scale,
             active_shape: None,
-            next_shape: Shape::random(),
+            active_rotation: 0,
+            next_shapes,
+            fixed_queue: false,
             held_shape: None,
             shapes: 0,
             score: 0,
             level: 1,
+            lines: 0,
+            last_clear: None,
+            combo: 0,
+            back_to_back: false,
+            pieces_locked: 0,
+            last_lock: Vec::new(),
+            last_move_rotation: false,
             paused: false,
+            clicked_column: None,
+            mode: Mode::Marathon,
+            visibility: Visibility::Visible,
+            reveal_until: 0,
+            lock_timer: None,
+            lowest_row: 0,
+            gravity_acc: 0,
+            spawn_delay: 0,
+            frames: 0,
+            soft_drop_cells: 0,
+            master: MasterState::default(),
+            finesse: Finesse::default(),
+            stats: Stats::default(),
+            animations: Animations::default(),
+            garbage: Garbage::new(seed, width),
+            randomizer,
+            rules,
         }
     }
 
+    /// a fixed queue that has run out leaves the grid without an active shape
     pub fn next(&mut self, next: Option<Shape>) -> bool {
+        let Some(shape) = self.next_shapes.pop_front() else {
+            self.active_shape = None;
+            return true;
+        };
+        self.next_shapes.extend(next);
+        while !self.fixed_queue && self.next_shapes.len() < self.rules.preview.max(1) {
+            self.next_shapes.push_back(self.randomizer.next());
+        }
+
         self.shapes += 1;
-        if self.shapes % 25 == 0 && self.level < 10 {
-            self.level += 1;
+        match self.mode {
+            Mode::Master => self.master_piece_spawned(),
+            _ => {
+                let every = self.rules.pieces_per_level;
+                if every > 0 && self.shapes.is_multiple_of(every) && self.level < self.rules.max_level {
+                    self.level += 1;
+                }
+            }
         }
-        self.active_shape = Some(self.next_shape);
-        self.next_shape = next.unwrap_or_else(Shape::random);
-        self.spawn(self.active_shape.unwrap())
+        self.active_shape = Some(shape);
+        self.spawn(shape)
     }
 
     pub fn set(&mut self, (x, y): (usize, usize), block: Block) {
         self.rows[y].cells[x] = block;
     }
 
-    /// returns whether or not the shape was successfully moved down. any impossibilities are handled by the game end callback provided
-    pub fn bring_down(&mut self, next: Option<Shape>, mut end_cb: impl FnMut(Grid) -> ()) -> bool {
-        let mut active_blocks = Vec::new();
-        for (y, row) in self.rows.iter().enumerate() {
-            for (x, cell) in row.cells.iter().enumerate() {
-                if let Block::Active(shape) = *cell {
-                    active_blocks.push((x, y, shape));
-                }
+    /// whether the visibility modifier currently hides the block at the given position
+    pub fn is_hidden(&self, (x, y): (usize, usize)) -> bool {
+        self.rows[y].cells[x].is_full()
+            && self.frames >= self.reveal_until
+            && self.visibility.hides(self.rows[y].locked_at[x], self.frames)
+    }
+
+    pub fn speed(&self) -> Speed {
+        if !self.mode.has_gravity() {
+            return Speed {
+                lock_delay: NO_LOCK,
+                ..Default::default()
+            };
+        }
+
+        match self.mode {
+            Mode::Master => master::speed(self.level),
+            _ => self.rules.speed(self.level),
+        }
+    }
+
+    /// advances the game by one frame: spawns pieces once their delay runs out, applies gravity and locks landed pieces
+    pub fn tick(&mut self, mut end_cb: impl FnMut(Grid)) {
+        if self.paused {
+            return;
+        }
+        self.frames += 1;
+
+        if self.rules.goal.is_some_and(|goal| goal.reached(self)) {
+            end_cb(self.clone());
+            return;
+        }
+        if let Some(garbage) = self.rules.garbage {
+            let every = garbage.every_seconds as u64 * FRAMES_PER_SECOND as u64;
+            if every > 0 && self.frames.is_multiple_of(every) && !self.add_garbage(1, garbage.messiness) {
+                end_cb(self.clone());
+                return;
             }
         }
 
-        let mut new_active_blocks = Vec::with_capacity(active_blocks.len());
+        if self.active_shape.is_none() {
+            self.spawn_delay = self.spawn_delay.saturating_sub(1);
+            if self.spawn_delay == 0 {
+                self.spawn_next(None, end_cb);
+            }
+            return;
+        }
 
-        for (x, y, shape) in &active_blocks {
-            new_active_blocks.push((*x, *y + 1, *shape));
+        let speed = self.speed();
+        self.gravity_acc += speed.gravity;
+        while self.gravity_acc >= GRAVITY_UNIT {
+            self.gravity_acc -= GRAVITY_UNIT;
+            if !self.bring_down(None, &mut end_cb) {
+                self.gravity_acc = 0;
+                break;
+            }
         }
 
-        for (x, y, _) in &new_active_blocks {
-            if *y == ROW_HEIGHT - 1 || matches!(self.rows[y + 1].cells[*x], Block::Full(_)) {
-                for (x, y, _) in &active_blocks {
-                    self.rows[*y].cells[*x] = Block::Empty;
-                }
-                for (x, y, shape) in &new_active_blocks {
-                    self.rows[*y].cells[*x] = Block::Full(*shape);
-                }
-                
-                if self.rows[0].cells.iter().any(|cell| cell.is_full()) || self.rows[1].cells.iter().any(|cell| cell.is_full()) {
-                    end_cb(self.clone());
-                } else {
-                    self.next(next);
-                }
+        // the timer only runs while the piece is resting on something
+        if let Some(timer) = self.lock_timer {
+            if !self.is_grounded(&self.active_blocks()) {
+                return;
+            }
+            if timer + 1 >= speed.lock_delay {
+                self.lock(None, end_cb);
+            } else {
+                self.lock_timer = Some(timer + 1);
+            }
+        }
+    }
 
-                return false;
+    /// returns whether or not the shape was successfully moved down. any impossibilities are handled by the game end callback provided
+    pub fn bring_down(&mut self, next: Option<Shape>, mut end_cb: impl FnMut(Grid)) -> bool {
+        let active_blocks = self.active_blocks();
+        if active_blocks.is_empty() {
+            return false;
+        }
+
+        if self.is_grounded(&active_blocks) {
+            if self.speed().lock_delay == 0 {
+                self.lock(next, end_cb);
+            } else {
+                self.lock_timer.get_or_insert(0);
             }
+            return false;
+        }
+
+        let mut new_active_blocks = Vec::with_capacity(active_blocks.len());
+
+        for (x, y, shape) in &active_blocks {
+            new_active_blocks.push((*x, *y + 1, *shape));
         }
 
         for (x, y, _) in &active_blocks {
@@ -87,8 +254,23 @@ impl Grid {
         for (x, y, shape) in &new_active_blocks {
             self.rows[*y].cells[*x] = Block::Active(*shape);
         }
+        self.last_move_rotation = false;
 
-        if self.rows[0].cells.iter().any(|cell| cell.is_full()) || self.rows[1].cells.iter().any(|cell| cell.is_full()) {
+        let bottom = new_active_blocks.iter().map(|(_, y, _)| *y).max().unwrap_or(0);
+        if bottom > self.lowest_row {
+            self.lowest_row = bottom;
+            self.lock_timer = None;
+        }
+
+        if self.is_grounded(&new_active_blocks) {
+            if self.speed().lock_delay == 0 {
+                self.lock(next, end_cb);
+                return false;
+            }
+            self.lock_timer.get_or_insert(0);
+        }
+
+        if self.topped_out() && self.mode.can_top_out() {
             end_cb(self.clone());
             return false;
         }
@@ -96,47 +278,182 @@ impl Grid {
         true
     }
 
-    pub fn move_active_blocks(&mut self, dx: i32, dy: i32) {
-        let mut active_blocks = Vec::new();
-        for (y, row) in self.rows.iter().enumerate() {
-            for (x, cell) in row.cells.iter().enumerate() {
+    /// locks a landed piece straight away instead of waiting out its lock delay
+    pub fn lock_active(&mut self, end_cb: impl FnMut(Grid)) {
+        if self.lock_timer.is_some() {
+            self.lock(None, end_cb);
+        }
+    }
+
+    fn lock(&mut self, next: Option<Shape>, mut end_cb: impl FnMut(Grid)) {
+        let t_spin = self.is_t_spin();
+        self.judge_finesse();
+        self.last_lock = self.active_blocks();
+        self.pieces_locked += 1;
+        if let Some(shape) = self.active_shape {
+            *self.stats.placed.entry(shape).or_default() += 1;
+        }
+        for row in self.rows.iter_mut() {
+            for (cell, locked_at) in row.cells.iter_mut().zip(row.locked_at.iter_mut()) {
                 if let Block::Active(shape) = *cell {
-                    active_blocks.push((x, y, shape));
+                    *cell = Block::Full(shape);
+                    *locked_at = self.frames;
                 }
             }
         }
+        self.lock_timer = None;
 
-        let mut new_active_blocks = Vec::with_capacity(active_blocks.len());
+        if self.topped_out() {
+            if self.mode.can_top_out() {
+                end_cb(self.clone());
+                return;
+            }
+            self.clear_top_rows();
+        }
 
-        for (x, y, shape) in &active_blocks {
-            new_active_blocks.push((
-                x.wrapping_add(dx as usize),
-                y.wrapping_add(dy as usize),
-                *shape,
-            ));
+        let score = self.score;
+        let board = self.rows.clone();
+        let rows = self.remove_full_rows();
+        let lines = rows.len() as u32;
+        if lines > 0 {
+            self.reveal_until = self.frames + REVEAL_FRAMES;
+            let cleared = rows.iter().map(|y| (*y, board[*y].clone())).collect();
+            self.animations.cleared(self.frames, self.speed().line_clear_delay, cleared);
+        }
+        self.animations.locked(self.frames, &self.last_lock);
+        self.lines += lines;
+
+        let difficult = lines == 4 || (t_spin && lines > 0);
+        let back_to_back = lines > 0 && difficult && self.back_to_back;
+        if lines > 0 {
+            self.combo += 1;
+            self.back_to_back = difficult;
+        } else {
+            self.combo = 0;
+        }
+        self.last_clear = (lines > 0 || t_spin).then(|| {
+            let mut clear = Clear {
+                lines,
+                rows,
+                t_spin,
+                perfect: lines > 0 && self.rows.iter().all(|row| row.cells.iter().all(|cell| !cell.is_full())),
+                points: self.score - score,
+                back_to_back,
+                combo: self.combo,
+                attack: 0,
+            };
+            clear.attack = versus::attack(&clear);
+            clear
+        });
+        if let (Some(clear), Some((_, _, shape))) = (&self.last_clear, self.last_lock.first()) {
+            self.animations.acted(self.frames, clear.labels(*shape));
         }
 
-        for (x, y, _) in &new_active_blocks {
-            if *x >= ROW_WIDTH
-                || *y >= ROW_HEIGHT
-                || matches!(self.rows[*y].cells[*x], Block::Full(_))
-            {
+        self.garbage.send(self.last_clear.as_ref().map_or(0, |clear| clear.attack));
+        if lines == 0 && !self.receive_garbage() {
+            end_cb(self.clone());
+            return;
+        }
+        if self.mode == Mode::Master {
+            self.master_piece_locked(lines);
+            if self.level >= master::MAX_LEVEL {
+                end_cb(self.clone());
+                return;
+            }
+        }
+        if let Some(garbage) = self.rules.garbage {
+            let every = garbage.every_pieces;
+            if every > 0 && self.pieces_locked.is_multiple_of(every) && !self.add_garbage(1, garbage.messiness) {
+                end_cb(self.clone());
                 return;
             }
         }
 
-        for (x, y, _) in &active_blocks {
-            self.rows[*y].cells[*x] = Block::Empty;
+        let speed = self.speed();
+        let delay = speed.are + if lines > 0 { speed.line_clear_delay } else { 0 };
+        if delay > 0 {
+            self.active_shape = None;
+            self.spawn_delay = delay;
+        } else {
+            self.spawn_next(next, end_cb);
         }
+    }
 
-        for (x, y, shape) in &new_active_blocks {
-            self.rows[*y].cells[*x] = Block::Active(*shape);
+    /// like `next`, but a piece that can't spawn tops the game out
+    fn spawn_next(&mut self, next: Option<Shape>, mut end_cb: impl FnMut(Grid)) {
+        if self.next(next) {
+            return;
+        }
+
+        if self.mode.can_top_out() {
+            end_cb(self.clone());
+        } else if let Some(shape) = self.active_shape {
+            self.clear_top_rows();
+            self.spawn(shape);
         }
     }
 
-    pub fn rotate_active_block(&mut self) {
-        let mut active_blocks = Vec::new();
+    /// three-corner rule: a spin piece like the T that was rotated into place with at least three of the corners around its centre filled
+    fn is_t_spin(&self) -> bool {
+        let spin = self.active_shape.and_then(|shape| self.rules.piece(shape)).is_some_and(|piece| piece.spin);
+        if !spin || !self.last_move_rotation {
+            return false;
+        }
 
+        let Some((x, y)) = self.active_origin() else {
+            return false;
+        };
+
+        let corners = [(-1, -1), (1, -1), (-1, 1), (1, 1)]
+            .iter()
+            .filter(|(dx, dy)| {
+                let (cx, cy) = (x + dx, y + dy);
+                cx < 0
+                    || cy < 0
+                    || cx >= self.width as i32
+                    || cy >= self.height as i32
+                    || self.rows[cy as usize].cells[cx as usize].is_full()
+            })
+            .count();
+
+        corners >= 3
+    }
+
+    /// the point the active piece turns around, worked out from where its cells are in its current state
+    fn active_origin(&self) -> Option<(i32, i32)> {
+        let piece = self.rules.piece(self.active_shape?)?;
+        let state = piece.rotations.get(self.active_rotation)?;
+        let blocks = self.active_blocks();
+        let min_x = blocks.iter().map(|(x, _, _)| *x as i32).min()?;
+        let min_y = blocks.iter().map(|(_, y, _)| *y as i32).min()?;
+
+        Some((
+            min_x - state.iter().map(|(x, _)| *x).min()?,
+            min_y - state.iter().map(|(_, y)| *y).min()?,
+        ))
+    }
+
+    pub fn active_positions(&self) -> Vec<(usize, usize)> {
+        self.active_blocks().iter().map(|(x, y, _)| (*x, *y)).collect()
+    }
+
+    /// where the active piece would end up if it was hard dropped
+    pub fn ghost_blocks(&self) -> Vec<(usize, usize)> {
+        let mut blocks = self.active_positions();
+        if blocks.is_empty() {
+            return blocks;
+        }
+
+        while blocks.iter().all(|(x, y)| *y + 1 < self.height && !self.rows[y + 1].cells[*x].is_full()) {
+            for (_, y) in blocks.iter_mut() {
+                *y += 1;
+            }
+        }
+        blocks
+    }
+
+    pub fn active_blocks(&self) -> Vec<(usize, usize, Shape)> {
+        let mut active_blocks = Vec::new();
         for (y, row) in self.rows.iter().enumerate() {
             for (x, cell) in row.cells.iter().enumerate() {
                 if let Block::Active(shape) = *cell {
@@ -144,43 +461,59 @@ impl Grid {
                 }
             }
         }
+        active_blocks
+    }
 
-        let shape = match self.active_shape {
-            Some(shape) => shape,
-            None => return,
-        };
+    /// whether any of the given blocks is resting on the floor or on a locked block
+    fn is_grounded(&self, blocks: &[(usize, usize, Shape)]) -> bool {
+        blocks
+            .iter()
+            .any(|(x, y, _)| *y == self.height - 1 || self.rows[y + 1].cells[*x].is_full())
+    }
 
-        let centre = match shape {
-            Shape::I => (active_blocks[1].0, active_blocks[1].1),
-            Shape::O => return,
-            _ => {
-                let min_x = active_blocks.iter().map(|(x, _, _)| x).min().unwrap();
-                let max_x = active_blocks.iter().map(|(x, _, _)| x).max().unwrap();
-                let min_y = active_blocks.iter().map(|(_, y, _)| y).min().unwrap();
-                let max_y = active_blocks.iter().map(|(_, y, _)| y).max().unwrap();
+    /// a block in the top two rows of the screen, which is one row of the board in big mode
+    fn topped_out(&self) -> bool {
+        self.rows.iter().take(TOP_OUT_ROWS.div_ceil(self.scale)).any(|row| row.cells.iter().any(|cell| cell.is_full()))
+    }
 
-                ((min_x + max_x) / 2, (min_y + max_y) / 2)
+    /// used instead of a game over in modes without top-out: wipes the locked blocks out of the spawn area
+    fn clear_top_rows(&mut self) {
+        for row in self.rows.iter_mut().take(ZEN_CLEAR_ROWS) {
+            for cell in row.cells.iter_mut() {
+                if cell.is_full() {
+                    *cell = Block::Empty;
+                }
             }
-        };
+        }
+    }
 
-        let rotation_matrix = [[0, 1], [-1, 0]];
+    pub fn move_active_blocks(&mut self, dx: i32, dy: i32) {
+        let mut active_blocks = Vec::new();
+        for (y, row) in self.rows.iter().enumerate() {
+            for (x, cell) in row.cells.iter().enumerate() {
+                if let Block::Active(shape) = *cell {
+                    active_blocks.push((x, y, shape));
+                }
+            }
+        }
 
         let mut new_active_blocks = Vec::with_capacity(active_blocks.len());
 
-        for (x, y, shape) in active_blocks.clone() {
-            let x = x as i32 - centre.0 as i32;
-            let y = y as i32 - centre.1 as i32;
-            let new_x = x * rotation_matrix[0][0] + y * rotation_matrix[0][1];
-            let new_y = x * rotation_matrix[1][0] + y * rotation_matrix[1][1];
-
-            let new_x = new_x + centre.0 as i32;
-            let new_y = new_y + centre.1 as i32;
+        for (x, y, shape) in &active_blocks {
+            new_active_blocks.push((
+                x.wrapping_add(dx as usize),
+                y.wrapping_add(dy as usize),
+                *shape,
+            ));
+        }
 
-            if new_x < 0 || new_x >= ROW_WIDTH as i32 || new_y < 0 || new_y >= ROW_HEIGHT as i32 {
+        for (x, y, _) in &new_active_blocks {
+            if *x >= self.width
+                || *y >= self.height
+                || self.rows[*y].cells[*x].is_full()
+            {
                 return;
             }
-
-            new_active_blocks.push((new_x as usize, new_y as usize, shape));
         }
 
         for (x, y, _) in &active_blocks {
@@ -190,53 +523,104 @@ impl Grid {
         for (x, y, shape) in &new_active_blocks {
             self.rows[*y].cells[*x] = Block::Active(*shape);
         }
+        self.last_move_rotation = false;
     }
 
-    pub fn remove_full_rows(&mut self) {
+    pub fn rotate_active_block(&mut self) {
+        self.turn_active_block(true);
+    }
+
+    pub fn rotate_active_block_ccw(&mut self) {
+        self.turn_active_block(false);
+    }
+
+    /// moves the active piece into its next rotation state, trying the piece's kicks if it doesn't fit where it is
+    fn turn_active_block(&mut self, clockwise: bool) {
+        let (Some(shape), Some((x, y))) = (self.active_shape, self.active_origin()) else {
+            return;
+        };
+        let Some(piece) = self.rules.piece(shape) else {
+            return;
+        };
+        if piece.rotations.len() < 2 {
+            return;
+        }
+
+        let (state, kicks) = piece.turn(self.active_rotation, clockwise);
+        let cells = &piece.rotations[state];
+        let fits = |(dx, dy): (i32, i32)| {
+            cells
+                .iter()
+                .map(|(cx, cy)| (x + dx + cx, y + dy + cy))
+                .map(|(x, y)| {
+                    (x >= 0 && y >= 0 && x < self.width as i32 && y < self.height as i32
+                        && !self.rows[y as usize].cells[x as usize].is_full())
+                    .then_some((x as usize, y as usize))
+                })
+                .collect::<Option<Vec<(usize, usize)>>>()
+        };
+        let Some(new_blocks) = std::iter::once((0, 0)).chain(kicks).find_map(fits) else {
+            return;
+        };
+
+        for (x, y, _) in self.active_blocks() {
+            self.rows[y].cells[x] = Block::Empty;
+        }
+        for (x, y) in new_blocks {
+            self.rows[y].cells[x] = Block::Active(shape);
+        }
+        self.active_rotation = state;
+        self.last_move_rotation = true;
+    }
+
+    /// returns the indices of the rows that were cleared
+    pub fn remove_full_rows(&mut self) -> Vec<usize> {
         let mut full_rows = Vec::new();
 
         for (y, row) in self.rows.iter().enumerate() {
-            if row.cells.iter().all(|cell| matches!(cell, Block::Full(_))) {
+            if row.cells.iter().all(|cell| cell.is_full()) {
                 full_rows.push(y);
             }
         }
 
         let num_full_rows = full_rows.len();
-        if num_full_rows > 0 {
-            self.score += match num_full_rows {
-                1 => 40 * self.level,
-                2 => 100 * self.level,
-                3 => 300 * self.level,
-                4 => 1200 * self.level,
-                _ => 0,
-            } as u32;
-        }
 
         for row in full_rows.iter() {
             for y in (0..*row).rev() {
                 self.rows[y + 1] = self.rows[y].clone();
             }
-            self.rows[0] = Row {
-                cells: [Block::Empty; ROW_WIDTH],
+            self.rows[0] = Row::empty(self.width);
+        }
+
+        if num_full_rows > 0 {
+            self.score += match self.mode {
+                Mode::Master => self.master_clear_score(num_full_rows as u32),
+                _ => self.rules.points(num_full_rows) * self.level,
             };
         }
+
+        full_rows
     }
 
+    /// a piece that isn't in the rule set's pieces can't spawn, the same as one with no room
     pub fn spawn(&mut self, shape: Shape) -> bool {
         self.remove_full_rows();
-        let x = ROW_WIDTH / 2;
-        let y = 0;
-
-        let positions = match shape {
-            Shape::I => vec![(x, y), (x, y + 1), (x, y + 2), (x, y + 3)],
-            Shape::O => vec![(x, y), (x + 1, y), (x, y + 1), (x + 1, y + 1)],
-            Shape::T => vec![(x, y), (x - 1, y), (x + 1, y), (x, y + 1)],
-            Shape::S => vec![(x, y), (x + 1, y), (x, y + 1), (x - 1, y + 1)],
-            Shape::Z => vec![(x, y), (x - 1, y), (x, y + 1), (x + 1, y + 1)],
-            Shape::J => vec![(x, y), (x - 1, y), (x + 1, y), (x + 1, y + 1)],
-            Shape::L => vec![(x, y), (x - 1, y), (x + 1, y), (x - 1, y + 1)],
+        let Some(piece) = self.rules.piece(shape) else {
+            return false;
         };
+        let x = (self.width / 2) as i32 + piece.spawn.0;
+        let y = piece.spawn.1;
 
+        let positions = piece.rotations[0]
+            .iter()
+            .map(|(cx, cy)| (x + cx, y + cy))
+            .filter(|(x, y)| *x >= 0 && *y >= 0 && *x < self.width as i32 && *y < self.height as i32)
+            .map(|(x, y)| (x as usize, y as usize))
+            .collect::<Vec<(usize, usize)>>();
+
+        if positions.len() < piece.rotations[0].len() {
+            return false;
+        }
         for &(x, y) in &positions {
             if self.rows[y].cells[x] != Block::Empty {
                 return false;
@@ -248,14 +632,25 @@ impl Grid {
         }
 
         self.active_shape = Some(shape);
-
-        // we can safely give a useless callback here
-        self.bring_down(None, |_| {});
+        self.active_rotation = 0;
+        self.soft_drop_cells = 0;
+        self.lock_timer = None;
+        self.lowest_row = 0;
+        self.last_move_rotation = false;
+        self.clicked_column = None;
+        self.finesse.inputs.clear();
+        self.finesse.held = false;
+
+        // nudge the piece down without locking it, a piece with nowhere to go is left for gravity to lock
+        self.move_active_blocks(0, 1);
 
         true
     }
 
     pub fn hold(&mut self) {
+        if !self.rules.hold {
+            return;
+        }
         if let Some(active_shape) = self.active_shape.take() {
             // replace the active shape with the held shape
 
@@ -272,6 +667,7 @@ impl Grid {
             } else {
                 self.next(None);
             }
+            self.finesse.held = true;
         }
     }
 }
@@ -1,72 +1,162 @@
 // filepath: /tetris/tetris/src/main.rs
 
 use std::io::{self, Write};
-use std::time::{Duration, Instant};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use clap::Parser;
-use config::Config;
+use config::{Config, GameSettings};
 use crossterm::{event, terminal, ExecutableCommand};
 
+mod animation;
 mod grid;
 mod config;
+mod drill;
+mod finesse;
+mod garbage;
+mod master;
+mod mode;
+mod net;
+mod puzzle;
+mod menu;
+mod randomizer;
+mod replay;
+mod royale;
+mod rules;
+mod scores;
+mod speed;
+mod stats;
+mod theme;
 mod shape;
+mod spectate;
 mod block;
+mod bot;
+mod clear;
 mod ui;
 mod utils;
+mod versus;
 mod constants {
     pub const ROW_WIDTH: usize = 10;
     pub const ROW_HEIGHT: usize = 20;
-
-    pub const I: &str = "██\n██\n██\n██";
-    pub const O: &str = "████\n████";
-    pub const T: &str = "██████\n  ██";
-    pub const S: &str = "  ████\n████";
-    pub const Z: &str = "████\n  ████";
-    pub const L: &str = "██████\n██";
-    pub const J: &str = "██████\n    ██";
 }
 
 
 use grid::Grid;
-use utils::{handle_events, end_game};
-
-fn run(terminal: &mut ratatui::DefaultTerminal, opts: Options, cfg: Config) -> Result<(), String> {
-    let mut grid = Grid::new();
-    let mut last_update = Instant::now();
-
-    if opts.level.unwrap_or(1) > 10 || opts.level.unwrap_or(1) == 0 {
-        panic!("0 < level <= 10 not met");
-    }
-
-    grid.level = opts.level.unwrap_or(1);
+use menu::Paused;
+use mode::{Mode, Visibility};
+use replay::Replay;
+use rules::RuleSet;
+use scores::HighScores;
+use spectate::Publisher;
+use speed::FRAMES_PER_SECOND;
+use theme::Theme;
+use utils::{handle_events, end_game, wait_for_room, Round};
 
-    let mut update_interval = Duration::from_millis((500.0 * ((11.0 - (grid.level as f32)) / 10.0)).ceil() as u64);
+/// plays a single player game until it ends or the quit key is pressed, and returns the final board
+/// and its place in the high scores if it made it in. the game is also saved as a replay. restarting
+/// from the pause menu starts a new game in its place, which leaves no replay or score behind
+fn run(terminal: &mut ratatui::DefaultTerminal, settings: &GameSettings, cfg: &Config, config_file: &str, publisher: &mut Option<Publisher>) -> Result<(Grid, Option<usize>), String> {
+    let mut cfg = cfg.clone();
+    utils::restarting(|| play(terminal, settings, &mut cfg, config_file, publisher))
+}
 
-    grid.next(None);
+/// one game for `run`, up to its end or a restart
+fn play(terminal: &mut ratatui::DefaultTerminal, settings: &GameSettings, cfg: &mut Config, config_file: &str, publisher: &mut Option<Publisher>) -> Result<Round<(Grid, Option<usize>)>, String> {
+    let mut rules = load_rules(settings.rules.as_deref(), settings.pieces.as_deref(), RuleSet::default())?;
+    // a replay needs the seed to deal the same pieces again
+    rules.seed = Some(rules.seed.unwrap_or_else(rand::random));
+    let mut grid = new_game(settings, rules.clone())?;
+    grid.finesse.judged = cfg.shows_faults();
+    let mut actions = Vec::new();
+    let mut ended = None;
+    let frame_time = Duration::from_secs(1) / FRAMES_PER_SECOND;
+    let mut last_frame = Instant::now();
 
-    loop {
+    while ended.is_none() {
+        if wait_for_room(terminal, cfg, ui::min_size(&grid, &cfg.theme), &mut last_frame)? {
+            ended = Some(grid.clone());
+            break;
+        }
         terminal
-            .draw(|frame| ui::draw(frame, grid.clone()))
+            .draw(|frame| ui::draw(frame, grid.clone(), cfg))
             .map_err(|e| e.to_string())?;
+        if let Some(publisher) = publisher {
+            publisher.publish(&[&grid]);
+        }
 
-        if last_update.elapsed() >= update_interval && !grid.paused {
-            grid.bring_down(None, |g| {
-                end_game(&g);
+        while last_frame.elapsed() >= frame_time && ended.is_none() {
+            last_frame += frame_time;
+            grid.tick(|g| {
+                ended.get_or_insert(g);
             });
+        }
 
-            last_update = Instant::now();
-            update_interval = Duration::from_millis((500.0 * ((11.0 - (grid.level as f32)) / 10.0)).ceil() as u64);
+        while ended.is_none() && event::poll(Duration::from_millis(1)).unwrap() {
+            let quit = handle_events(&mut grid, |g| {
+                ended.get_or_insert(g);
+            }, cfg, terminal, &mut actions)?;
+            if quit {
+                ended = Some(grid.clone());
+            }
         }
 
-        while event::poll(Duration::from_millis(1)).unwrap() {
-            if handle_events(&mut grid, |g| {
-                end_game(&g);
-            }, cfg.clone(), terminal)? {
-                cleanup_terminal();
-                std::process::exit(0);
+        if grid.paused && ended.is_none() {
+            match utils::pause(terminal, &mut grid, cfg, config_file, &mut last_frame)? {
+                Paused::Resume => {}
+                Paused::Restart => return Ok(Round::Restarted),
+                Paused::Quit => ended = Some(grid.clone()),
             }
         }
     }
+
+    let grid = ended.unwrap();
+    let played_at = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.as_secs());
+    // a read-only directory shouldn't take the end of the game with it
+    let _ = Replay { settings: settings.clone(), rules, actions, frames: grid.frames, score: grid.score, played_at }.save();
+    let mut scores = HighScores::load();
+    let place = scores.record(&grid);
+    if place.is_some() {
+        let _ = scores.save();
+    }
+    Ok(Round::Over((grid, place)))
+}
+
+/// a board set up for a single player game with its first piece out
+fn new_game(settings: &GameSettings, mut rules: RuleSet) -> Result<Grid, String> {
+    let scale = if settings.big { 2 } else { 1 };
+    rules.validate(scale)?;
+    if settings.level > rules.max_level || settings.level == 0 {
+        return Err(format!("0 < level <= {} not met", rules.max_level));
+    }
+
+    let mut grid = Grid::with_rules(rules, scale);
+    grid.mode = if settings.rules.is_some() { Mode::Custom } else { settings.mode };
+    grid.visibility = settings.visibility;
+    grid.level = match grid.mode {
+        // master always counts up from 0
+        Mode::Master => 0,
+        _ => settings.level,
+    };
+
+    grid.next(None);
+    Ok(grid)
+}
+
+/// the rules from a rule set file, or `fallback`, with the pieces from a piece set file if there is one
+fn load_rules(rules: Option<&Path>, pieces: Option<&Path>, fallback: RuleSet) -> Result<RuleSet, String> {
+    let mut rules = match rules {
+        Some(path) => RuleSet::load(path)?,
+        None => fallback,
+    };
+    if let Some(path) = pieces {
+        rules.pieces = shape::load_pieces(path)?;
+    }
+    Ok(rules)
+}
+
+/// the rules from `--rules`, or the standard versus rules
+fn versus_rules(opts: &Options) -> Result<RuleSet, String> {
+    load_rules(opts.rules.as_deref(), opts.pieces.as_deref(), RuleSet::versus())
 }
 
 fn cleanup_terminal() {
@@ -80,23 +170,130 @@ fn cleanup_terminal() {
 
 #[derive(Parser)]
 struct Options {
-    /// Start at a specific level (1-10)
+    /// Start at a specific level (1-10), ignored in master mode
     #[arg(short, long)]
     level: Option<u32>,
 
+    /// Game mode to play, marathon if it's left out. Without any game options the main menu opens instead
+    #[arg(short, long, value_enum)]
+    mode: Option<Mode>,
+
+    /// Play a custom mode from a rule set file instead of one of the built-in modes
+    #[arg(long, conflicts_with = "mode")]
+    rules: Option<PathBuf>,
+
+    /// Play with the pieces defined in a file instead of the tetrominoes
+    #[arg(long)]
+    pieces: Option<PathBuf>,
+
+    /// Stream the boards to watchers on a TCP `host:port` or `unix:<path>` socket. Royale only
+    /// streams your own board
+    #[arg(long)]
+    publish: Option<String>,
+
+    /// Play with pieces made of 2x2 cells
+    #[arg(long)]
+    big: bool,
+
+    /// Hide locked blocks as soon as they lock
+    #[arg(long, conflicts_with = "fade")]
+    invisible: bool,
+
+    /// Hide locked blocks this many seconds after they lock
+    #[arg(long)]
+    fade: Option<u32>,
+
+    /// Colour theme: guideline, classic, monochrome, high-contrast, colorblind, or a theme file
+    #[arg(long)]
+    theme: Option<String>,
+
     /// Path to the config file
     #[arg(short, long, default_value = "config.json")]
     config_file: String,
 
-    /// Create a configuration file
-    #[command(subcommand, name = "config")]
-    config: Option<CreateConfig>,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+impl Options {
+    /// whether any options were given for a single player game, otherwise there's the main menu
+    fn starts_game(&self) -> bool {
+        self.mode.is_some()
+            || self.rules.is_some()
+            || self.pieces.is_some()
+            || self.level.is_some()
+            || self.big
+            || self.invisible
+            || self.fade.is_some()
+    }
+
+    fn game_settings(&self) -> GameSettings {
+        GameSettings {
+            mode: self.mode.unwrap_or(Mode::Marathon),
+            rules: self.rules.clone(),
+            pieces: self.pieces.clone(),
+            level: self.level.unwrap_or(1),
+            visibility: match (self.invisible, self.fade) {
+                (true, _) => Visibility::Invisible,
+                (false, Some(seconds)) => Visibility::Fade(seconds),
+                (false, None) => Visibility::Visible,
+            },
+            big: self.big,
+        }
+    }
 }
 
 #[derive(Parser)]
-enum CreateConfig {
+enum Command {
     /// Create a new configuration file
     Config,
+    /// Play a puzzle file, or every puzzle in a directory
+    Puzzle {
+        path: PathBuf,
+    },
+    /// Play the built-in setup drills, or the drills in a file or directory
+    Drill {
+        path: Option<PathBuf>,
+    },
+    /// Two players on one keyboard sending each other garbage, `--rules` replaces the versus rules
+    Versus,
+    /// Versus against a bot, a difficulty preset can be fine tuned with the other options
+    Bot {
+        #[arg(short, long, value_enum, default_value_t = bot::Difficulty::Medium)]
+        difficulty: bot::Difficulty,
+        /// Most pieces the bot places per second
+        #[arg(long)]
+        pps: Option<f32>,
+        /// How many pieces the bot looks ahead, the current one included
+        #[arg(long)]
+        depth: Option<u32>,
+        /// Chance from 0 to 1 that the bot goes for a worse placement
+        #[arg(long)]
+        errors: Option<f32>,
+    },
+    /// Battle royale against a crowd of bots, the last board standing wins
+    Royale {
+        /// How many bots to play against
+        #[arg(short, long, default_value_t = 15)]
+        bots: usize,
+        #[arg(short, long, value_enum, default_value_t = bot::Difficulty::Easy)]
+        difficulty: bot::Difficulty,
+        /// Who your garbage goes to at the start, Tab cycles through them in game
+        #[arg(short, long, value_enum, default_value_t = royale::Targeting::Random)]
+        targeting: royale::Targeting,
+    },
+    /// Host a versus match over the network, `--rules` replaces the versus rules for both players
+    Host {
+        port: u16,
+    },
+    /// Join a versus match hosted at an address such as 127.0.0.1:4000
+    Join {
+        addr: String,
+    },
+    /// Watch a game started with `--publish` at a TCP `host:port` or `unix:<path>` address
+    Watch {
+        addr: String,
+    },
 }
 
 fn main() -> std::io::Result<()> {
@@ -105,14 +302,14 @@ fn main() -> std::io::Result<()> {
 
 
     // check if we need to create a new config file
-    let config = match opts.config {
-        Some(CreateConfig::Config) => {
+    let mut config = match opts.command {
+        Some(Command::Config) => {
             let cfg = config::interactive_config();
             let serialized = serde_json::to_string(&cfg).unwrap();
             std::fs::write("config.json", serialized).unwrap();
             cfg
         }
-        None => {
+        _ => {
             let file = std::fs::read_to_string(&opts.config_file);
             
             match file {
@@ -132,9 +329,63 @@ fn main() -> std::io::Result<()> {
 
 
 
-    let out = run(&mut terminal, opts, config);
-    if out.is_err() {
-        panic!("Error: {}", out.unwrap_err());
+    let publisher = opts.publish.as_deref().map(Publisher::bind).transpose();
+    let theme = opts.theme.as_deref().map(Theme::find).transpose();
+    let out = config.check_keys().and(theme).and_then(|theme| {
+        if let Some(theme) = theme {
+            config.theme = theme;
+        }
+        publisher
+    });
+    let out = out.and_then(|publisher| match &opts.command {
+        Some(Command::Puzzle { path }) => {
+            let path = path.clone();
+            puzzle::play(&mut terminal, &path, config, &opts.config_file)
+        }
+        Some(Command::Drill { path }) => {
+            let drills = match path {
+                Some(path) => drill::Drill::load_all(path),
+                None => Ok(drill::Drill::built_in()),
+            };
+            drills.and_then(|drills| drill::play(&mut terminal, drills, config, &opts.config_file))
+        }
+        Some(Command::Versus) => {
+            versus_rules(&opts).and_then(|rules| versus::play(&mut terminal, rules, config, &opts.config_file, None, publisher))
+        }
+        Some(Command::Bot { difficulty, pps, depth, errors }) => {
+            let mut settings = difficulty.settings();
+            settings.pps = pps.unwrap_or(settings.pps);
+            settings.depth = depth.unwrap_or(settings.depth);
+            settings.error_rate = errors.unwrap_or(settings.error_rate);
+            versus_rules(&opts).and_then(|rules| versus::play(&mut terminal, rules, config, &opts.config_file, Some(settings), publisher))
+        }
+        Some(Command::Royale { bots, difficulty, targeting }) => versus_rules(&opts).and_then(|rules| {
+            let field = royale::Field {
+                bots: (*bots).max(1),
+                bot: difficulty.settings(),
+                targeting: *targeting,
+            };
+            royale::play(&mut terminal, rules, config, &opts.config_file, field, publisher)
+        }),
+        Some(Command::Host { port }) => {
+            versus_rules(&opts).and_then(|rules| net::host(&mut terminal, *port, rules, config, publisher))
+        }
+        Some(Command::Join { addr }) => net::join(&mut terminal, addr, config, publisher),
+        Some(Command::Watch { addr }) => spectate::watch(&mut terminal, addr, config),
+        _ if opts.starts_game() => {
+            let mut publisher = publisher;
+            run(&mut terminal, &opts.game_settings(), &config, &opts.config_file, &mut publisher).map(|(grid, _)| end_game(&grid, &config.theme))
+        }
+        _ => {
+            // the menu binds the address again for each game it starts
+            drop(publisher);
+            menu::show(&mut terminal, config, &opts.config_file, opts.publish.clone())
+        }
+    });
+    cleanup_terminal();
+    if let Err(e) = out {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
     }
 
     Ok(())
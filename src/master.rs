@@ -0,0 +1,244 @@
+// src/master.rs
+//
+// TGM-style master mode: the level goes up by one for every piece and by the number of lines
+// for every clear, gravity ramps up to 20G and the delays shrink as the game goes on.
+
+use crate::grid::Grid;
+use crate::speed::{Speed, FRAMES_PER_SECOND};
+
+pub const MAX_LEVEL: u32 = 999;
+
+/// (level, gravity) pairs, the gravity applies from that level until the next entry
+const GRAVITY: [(u32, u32); 30] = [
+    (0, 4),
+    (30, 6),
+    (35, 8),
+    (40, 10),
+    (50, 12),
+    (60, 16),
+    (70, 32),
+    (80, 48),
+    (90, 64),
+    (100, 80),
+    (120, 96),
+    (140, 112),
+    (160, 128),
+    (170, 144),
+    (200, 4),
+    (220, 32),
+    (230, 64),
+    (233, 96),
+    (236, 128),
+    (239, 160),
+    (243, 192),
+    (247, 224),
+    (251, 256),
+    (300, 512),
+    (330, 768),
+    (360, 1024),
+    (400, 1280),
+    (420, 1024),
+    (450, 768),
+    (500, 5120),
+];
+
+/// (level, are, lock delay, line clear delay)
+const DELAYS: [(u32, u32, u32, u32); 6] = [
+    (0, 25, 30, 40),
+    (500, 25, 30, 25),
+    (600, 25, 30, 16),
+    (700, 16, 30, 12),
+    (800, 12, 30, 6),
+    (900, 12, 17, 6),
+];
+
+/// minimum score for each grade, from worst to best
+const GRADES: [(u32, &str); 18] = [
+    (0, "9"),
+    (400, "8"),
+    (800, "7"),
+    (1400, "6"),
+    (2000, "5"),
+    (3500, "4"),
+    (5500, "3"),
+    (8000, "2"),
+    (12000, "1"),
+    (16000, "S1"),
+    (22000, "S2"),
+    (30000, "S3"),
+    (40000, "S4"),
+    (52000, "S5"),
+    (66000, "S6"),
+    (82000, "S7"),
+    (100000, "S8"),
+    (120000, "S9"),
+];
+
+/// (level, minimum score, time limit in frames) that must all be met to be awarded GM
+const GM_CHECKPOINTS: [(u32, u32, u32); 3] = [
+    (300, 12000, 4 * 60 * FRAMES_PER_SECOND + 15 * FRAMES_PER_SECOND),
+    (500, 40000, 7 * 60 * FRAMES_PER_SECOND),
+    (MAX_LEVEL, 126000, 13 * 60 * FRAMES_PER_SECOND + 30 * FRAMES_PER_SECOND),
+];
+
+#[derive(Debug, Clone)]
+pub struct MasterState {
+    pub combo: u32,
+    /// frame count at which each section of 100 levels was finished
+    pub section_times: Vec<u64>,
+    pub gm_eligible: bool,
+}
+
+impl Default for MasterState {
+    fn default() -> Self {
+        MasterState {
+            combo: 1,
+            section_times: Vec::new(),
+            gm_eligible: true,
+        }
+    }
+}
+
+pub fn speed(level: u32) -> Speed {
+    let gravity = GRAVITY
+        .iter()
+        .rev()
+        .find(|(from, _)| level >= *from)
+        .map(|(_, gravity)| *gravity)
+        .unwrap_or(GRAVITY[0].1);
+    let (_, are, lock_delay, line_clear_delay) = *DELAYS
+        .iter()
+        .rev()
+        .find(|(from, ..)| level >= *from)
+        .unwrap_or(&DELAYS[0]);
+
+    Speed {
+        gravity,
+        lock_delay,
+        are,
+        line_clear_delay,
+    }
+}
+
+/// the level at which the current section stops, pieces alone can't push the level past it
+pub fn section_stop(level: u32) -> u32 {
+    if level >= 900 {
+        MAX_LEVEL - 1
+    } else {
+        level / 100 * 100 + 99
+    }
+}
+
+pub fn grade(grid: &Grid) -> &'static str {
+    if grid.level >= MAX_LEVEL && grid.master.gm_eligible {
+        return "GM";
+    }
+
+    GRADES
+        .iter()
+        .rev()
+        .find(|(score, _)| grid.score >= *score)
+        .map(|(_, grade)| *grade)
+        .unwrap_or(GRADES[0].1)
+}
+
+/// formats a frame count as `mm:ss.cc`
+pub fn format_time(frames: u64) -> String {
+    let centis = frames * 100 / FRAMES_PER_SECOND as u64;
+    format!("{:02}:{:02}.{:02}", centis / 6000, centis / 100 % 60, centis % 100)
+}
+
+impl Grid {
+    /// called once for every piece that enters the board
+    pub(crate) fn master_piece_spawned(&mut self) {
+        if self.level < section_stop(self.level) {
+            self.advance_master_level(1);
+        }
+    }
+
+    /// called when a piece locks, with the number of lines it cleared
+    pub(crate) fn master_piece_locked(&mut self, lines: u32) {
+        if lines == 0 {
+            self.master.combo = 1;
+        } else {
+            self.advance_master_level(lines);
+        }
+    }
+
+    /// score for a clear: `(ceil((level + lines) / 4) + soft) * lines * combo * bravo`
+    pub(crate) fn master_clear_score(&mut self, lines: u32) -> u32 {
+        self.master.combo += 2 * lines - 2;
+        let bravo = if self.rows.iter().all(|row| row.cells.iter().all(|cell| !cell.is_full())) {
+            4
+        } else {
+            1
+        };
+
+        ((self.level + lines).div_ceil(4) + self.soft_drop_cells) * lines * self.master.combo * bravo
+    }
+
+    fn advance_master_level(&mut self, by: u32) {
+        let before = self.level;
+        self.level = (self.level + by).min(MAX_LEVEL);
+
+        if self.level != before && (self.level / 100 > before / 100 || self.level == MAX_LEVEL) {
+            self.master.section_times.push(self.frames);
+        }
+
+        for (level, score, frames) in GM_CHECKPOINTS {
+            if before < level && self.level >= level && (self.score < score || self.frames > frames as u64) {
+                self.master.gm_eligible = false;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grade_follows_the_score() {
+        let mut grid = Grid::new();
+        assert_eq!(grade(&grid), "9");
+        grid.score = 399;
+        assert_eq!(grade(&grid), "9");
+        grid.score = 400;
+        assert_eq!(grade(&grid), "8");
+        grid.score = 16000;
+        assert_eq!(grade(&grid), "S1");
+        grid.score = 1_000_000;
+        assert_eq!(grade(&grid), "S9");
+    }
+
+    #[test]
+    fn grand_master_needs_the_last_level_and_every_checkpoint() {
+        let mut grid = Grid::new();
+        grid.level = MAX_LEVEL;
+        assert_eq!(grade(&grid), "GM");
+        grid.master.gm_eligible = false;
+        assert_eq!(grade(&grid), "9");
+    }
+
+    #[test]
+    fn speed_uses_the_last_entry_at_or_below_the_level() {
+        let start = speed(0);
+        assert_eq!((start.gravity, start.are, start.lock_delay, start.line_clear_delay), (4, 25, 30, 40));
+        assert_eq!(speed(29).gravity, 4);
+        assert_eq!(speed(30).gravity, 6);
+        // gravity drops back down at 200 before it ramps up to 20G
+        assert_eq!(speed(199).gravity, 144);
+        assert_eq!(speed(200).gravity, 4);
+        assert_eq!(speed(500).gravity, 5120);
+
+        let end = speed(MAX_LEVEL);
+        assert_eq!((end.gravity, end.are, end.lock_delay, end.line_clear_delay), (5120, 12, 17, 6));
+    }
+
+    #[test]
+    fn sections_stop_one_short_of_the_next_hundred() {
+        assert_eq!(section_stop(0), 99);
+        assert_eq!(section_stop(450), 499);
+        assert_eq!(section_stop(950), MAX_LEVEL - 1);
+    }
+}
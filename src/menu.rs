@@ -0,0 +1,665 @@
+// src/menu.rs
+//
+// The main menu opens when the game is started without a subcommand or any game options. Whatever is
+// picked in it is saved to the config file, so next time it opens the way it was left. It's used
+// with the arrow keys, enter and escape, or with the mouse: a click picks an entry, the wheel moves
+// up and down, and in the settings left and right change the selected setting. The same menu, opened
+// on its pause screen, is what the pause key brings up in a single player game.
+
+use std::path::{Path, PathBuf};
+
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, MouseButton, MouseEventKind};
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Stylize};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, Clear, Paragraph};
+use ratatui::Frame;
+
+use crate::bot::Difficulty;
+use crate::config::{key_name, Config, KeyCodeWrapper};
+use crate::mode::{Mode, Visibility};
+use crate::replay::{self, Replay};
+use crate::royale::{self, Field, Targeting};
+use crate::rules::RuleSet;
+use crate::scores::HighScores;
+use crate::spectate::Publisher;
+use crate::theme::{EmptyCells, Glyphs, Theme, PRESETS};
+use crate::utils::{summary, wait_for_key};
+use crate::{drill, puzzle, ui, versus};
+
+const RULES_DIR: &str = "rules";
+const PIECES_DIR: &str = "pieces";
+const PUZZLES_DIR: &str = "puzzles";
+const MAIN: [&str; 6] = ["Play", "Settings", "Key bindings", "High scores", "Replays", "Quit"];
+const PAUSE: [&str; 5] = ["Resume", "Restart", "Settings", "Key bindings", "Quit to menu"];
+const SETTINGS: [&str; 13] = [
+    "Level",
+    "Visibility",
+    "Big pieces",
+    "Pieces",
+    "Theme",
+    "Cells",
+    "Empty cells",
+    "Help panel",
+    "Stats panel",
+    "Animations",
+    "Bot difficulty",
+    "Royale bots",
+    "Soft drop",
+];
+const KEYS: [&str; 9] = [
+    "Move left",
+    "Move right",
+    "Hard drop",
+    "Soft drop",
+    "Rotate clockwise",
+    "Rotate counter-clockwise",
+    "Hold",
+    "Pause",
+    "Quit",
+];
+const VISIBILITIES: [Visibility; 4] = [Visibility::Visible, Visibility::Fade(5), Visibility::Fade(10), Visibility::Invisible];
+const DIFFICULTIES: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Medium, Difficulty::Hard];
+/// highest level the menu offers to start on
+const MAX_START_LEVEL: u32 = 10;
+const MAX_BOTS: usize = 40;
+const PANEL_WIDTH: u16 = 56;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Screen {
+    Main,
+    Pause,
+    Play,
+    Settings,
+    Keys,
+    Scores,
+    Replays,
+}
+
+/// an entry of the play menu
+#[derive(Clone)]
+enum Game {
+    Mode(Mode),
+    Rules(PathBuf, String),
+    Puzzles,
+    Drills,
+    Versus,
+    Bot,
+    Royale,
+}
+
+impl Game {
+    fn label(&self) -> String {
+        match self {
+            Game::Mode(mode) => mode.name().to_string(),
+            Game::Rules(_, name) => name.clone(),
+            Game::Puzzles => "Puzzles".to_string(),
+            Game::Drills => "Drills".to_string(),
+            Game::Versus => "Versus".to_string(),
+            Game::Bot => "Versus a bot".to_string(),
+            Game::Royale => "Battle royale".to_string(),
+        }
+    }
+}
+
+/// what was picked in the pause menu
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Paused {
+    Resume,
+    Restart,
+    Quit,
+}
+
+struct Menu {
+    cfg: Config,
+    config_file: String,
+    publish: Option<String>,
+    /// the screen going back ends up on, and leaving it closes the menu
+    home: Screen,
+    screen: Screen,
+    selected: usize,
+    games: Vec<Game>,
+    scores: HighScores,
+    /// which high score table is shown
+    table: usize,
+    replays: Vec<Replay>,
+    /// the key binding waiting for a key press
+    binding: Option<usize>,
+    error: Option<String>,
+    /// what the pause menu was closed with
+    paused: Paused,
+}
+
+/// what an input does in the menu
+enum Input {
+    Up,
+    Down,
+    Left,
+    Right,
+    Pick(usize),
+    Back,
+}
+
+/// shows the menu until quit is picked
+pub fn show(terminal: &mut ratatui::DefaultTerminal, cfg: Config, config_file: &str, publish: Option<String>) -> Result<(), String> {
+    Menu::new(cfg, config_file, publish, Screen::Main).run(terminal)
+}
+
+/// shows the pause menu over an empty screen, so the board can't be studied while the game is paused.
+/// settings and key bindings changed in it are saved and carried back into the game through `cfg`
+pub fn pause(terminal: &mut ratatui::DefaultTerminal, cfg: &mut Config, config_file: &str) -> Result<Paused, String> {
+    let mut menu = Menu::new(cfg.clone(), config_file, None, Screen::Pause);
+    menu.run(terminal)?;
+    *cfg = menu.cfg;
+    Ok(menu.paused)
+}
+
+impl Menu {
+    fn new(cfg: Config, config_file: &str, publish: Option<String>, home: Screen) -> Menu {
+        Menu {
+            cfg,
+            config_file: config_file.to_string(),
+            publish,
+            home,
+            screen: home,
+            selected: 0,
+            games: games(),
+            scores: HighScores::default(),
+            table: 0,
+            replays: Vec::new(),
+            binding: None,
+            error: None,
+            paused: Paused::Resume,
+        }
+    }
+
+    /// handles input until the menu is left from its home screen
+    fn run(&mut self, terminal: &mut ratatui::DefaultTerminal) -> Result<(), String> {
+        loop {
+            let items = self.items();
+            terminal.draw(|frame| self.draw(frame, &items)).map_err(|e| e.to_string())?;
+
+            let event = event::read().map_err(|e| e.to_string())?;
+            if let Some(binding) = self.binding {
+                if let Event::Key(key) = event {
+                    if key.kind == KeyEventKind::Press {
+                        let before = std::mem::replace(binding_mut(&mut self.cfg, binding), KeyCodeWrapper { code: key.code });
+                        self.binding = None;
+                        match self.cfg.check_keys() {
+                            Ok(()) => self.save(),
+                            Err(e) => {
+                                *binding_mut(&mut self.cfg, binding) = before;
+                                self.error = Some(e);
+                            }
+                        }
+                    }
+                }
+                continue;
+            }
+
+            let input = match event {
+                Event::Key(key) if key.kind == KeyEventKind::Press => match key.code {
+                    KeyCode::Up | KeyCode::Char('k') => Input::Up,
+                    KeyCode::Down | KeyCode::Char('j') => Input::Down,
+                    KeyCode::Left | KeyCode::Char('h') => Input::Left,
+                    KeyCode::Right | KeyCode::Char('l') => Input::Right,
+                    KeyCode::Enter | KeyCode::Char(' ') => Input::Pick(self.selected),
+                    KeyCode::Esc | KeyCode::Backspace => Input::Back,
+                    code if code == self.cfg.quit.code => Input::Back,
+                    code if self.home == Screen::Pause && code == self.cfg.pause.code => Input::Back,
+                    _ => continue,
+                },
+                Event::Mouse(mouse) => match mouse.kind {
+                    MouseEventKind::Down(MouseButton::Left) => {
+                        let area = terminal.get_frame().area();
+                        match item_at(panel(area, items.len()), mouse.column, mouse.row, items.len()) {
+                            Some(index) => Input::Pick(index),
+                            None => continue,
+                        }
+                    }
+                    MouseEventKind::ScrollUp => Input::Up,
+                    MouseEventKind::ScrollDown => Input::Down,
+                    _ => continue,
+                },
+                _ => continue,
+            };
+            self.error = None;
+
+            let count = items.len().max(1);
+            match input {
+                Input::Up => self.selected = (self.selected + count - 1) % count,
+                Input::Down => self.selected = (self.selected + 1) % count,
+                Input::Left | Input::Right if self.screen == Screen::Settings => {
+                    self.change(self.selected, if matches!(input, Input::Left) { -1 } else { 1 });
+                }
+                Input::Left | Input::Right if self.screen == Screen::Scores => {
+                    let tables = self.scores.tables.len().max(1);
+                    self.table = match input {
+                        Input::Left => (self.table + tables - 1) % tables,
+                        _ => (self.table + 1) % tables,
+                    };
+                }
+                Input::Left => self.back(),
+                Input::Right => {}
+                Input::Pick(index) => {
+                    self.selected = index;
+                    if !self.pick(terminal, index, &items)? {
+                        return Ok(());
+                    }
+                }
+                Input::Back => {
+                    if self.screen == self.home {
+                        return Ok(());
+                    }
+                    self.back();
+                }
+            }
+        }
+    }
+
+    fn items(&self) -> Vec<String> {
+        let mut items = match self.screen {
+            Screen::Main => return MAIN.iter().map(|item| item.to_string()).collect(),
+            Screen::Pause => return PAUSE.iter().map(|item| item.to_string()).collect(),
+            Screen::Play => self.games.iter().map(Game::label).collect(),
+            Screen::Settings => SETTINGS
+                .iter()
+                .enumerate()
+                .map(|(index, name)| format!("{:<16}< {} >", name, self.setting(index)))
+                .collect(),
+            Screen::Keys => KEYS
+                .iter()
+                .enumerate()
+                .map(|(index, name)| {
+                    let key = match self.binding == Some(index) {
+                        true => "press a key".to_string(),
+                        false => key_name(binding(&self.cfg, index).code),
+                    };
+                    format!("{:<26}{}", name, key)
+                })
+                .collect(),
+            Screen::Scores => {
+                let table = self.scores.tables.values().nth(self.table);
+                let mut rows = table.map_or(Vec::new(), |scores| {
+                    scores
+                        .iter()
+                        .enumerate()
+                        .map(|(place, score)| format!("{:>2}. {}", place + 1, score.describe()))
+                        .collect::<Vec<String>>()
+                });
+                if rows.is_empty() {
+                    rows.push("No games yet".to_string());
+                }
+                rows
+            }
+            Screen::Replays => {
+                let mut replays = self.replays.iter().map(Replay::describe).collect::<Vec<String>>();
+                if replays.is_empty() {
+                    replays.push("No replays yet".to_string());
+                }
+                replays
+            }
+        };
+        items.push("Back".to_string());
+        items
+    }
+
+    fn title(&self) -> String {
+        match self.screen {
+            Screen::Main => "Tetris".to_string(),
+            Screen::Pause => "Paused".to_string(),
+            Screen::Play => "Play".to_string(),
+            Screen::Settings => "Settings".to_string(),
+            Screen::Keys => "Key bindings".to_string(),
+            Screen::Scores => match self.scores.tables.keys().nth(self.table) {
+                Some(name) => format!("High scores: {}", name),
+                None => "High scores".to_string(),
+            },
+            Screen::Replays => "Replays".to_string(),
+        }
+    }
+
+    fn hint(&self) -> &'static str {
+        match self.screen {
+            Screen::Settings => "↑↓ move  ←→ change  esc back",
+            Screen::Scores => "←→ other modes  esc back",
+            Screen::Keys => "enter rebind  esc back",
+            Screen::Pause => "↑↓ move  enter pick  esc resume",
+            _ => "↑↓ move  enter pick  esc back",
+        }
+    }
+
+    fn draw(&self, frame: &mut Frame, items: &[String]) {
+        let area = panel(frame.area(), items.len());
+        let mut lines = items
+            .iter()
+            .enumerate()
+            .map(|(index, item)| match index == self.selected {
+                true => Line::from(format!("> {}", item)).fg(Color::Yellow).bold(),
+                false => Line::from(format!("  {}", item)).fg(Color::White),
+            })
+            .collect::<Vec<Line>>();
+        lines.push(Line::from(""));
+        match &self.error {
+            Some(error) => lines.push(Line::from(error.clone()).fg(Color::Red)),
+            None => lines.push(Line::from(self.hint()).fg(Color::DarkGray)),
+        }
+
+        frame.render_widget(Clear, area);
+        frame.render_widget(
+            Paragraph::new(lines).block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_set(self.cfg.theme.cells().border())
+                    .title(self.title())
+                    .bold(),
+            ),
+            area,
+        );
+    }
+
+    fn open(&mut self, screen: Screen) {
+        self.screen = screen;
+        self.selected = 0;
+        match screen {
+            Screen::Play => {
+                self.games = games();
+                let last = &self.cfg.menu.last_played;
+                self.selected = self.games.iter().position(|game| game.label() == *last).unwrap_or(0);
+            }
+            Screen::Scores => {
+                self.scores = HighScores::load();
+                self.table = 0;
+            }
+            Screen::Replays => self.replays = Replay::load_all(),
+            _ => {}
+        }
+    }
+
+    fn back(&mut self) {
+        let from = self.screen;
+        self.open(self.home);
+        self.selected = match (self.home, from) {
+            (Screen::Pause, Screen::Settings) => 2,
+            (Screen::Pause, Screen::Keys) => 3,
+            (_, Screen::Main | Screen::Pause | Screen::Play) => 0,
+            (_, Screen::Settings) => 1,
+            (_, Screen::Keys) => 2,
+            (_, Screen::Scores) => 3,
+            (_, Screen::Replays) => 4,
+        };
+    }
+
+    /// returns false once quit is picked
+    fn pick(&mut self, terminal: &mut ratatui::DefaultTerminal, index: usize, items: &[String]) -> Result<bool, String> {
+        if self.screen != self.home && index + 1 == items.len() {
+            self.back();
+            return Ok(true);
+        }
+
+        match self.screen {
+            Screen::Main => match index {
+                0 => self.open(Screen::Play),
+                1 => self.open(Screen::Settings),
+                2 => self.open(Screen::Keys),
+                3 => self.open(Screen::Scores),
+                4 => self.open(Screen::Replays),
+                _ => return Ok(false),
+            },
+            Screen::Pause => match index {
+                2 => self.open(Screen::Settings),
+                3 => self.open(Screen::Keys),
+                _ => {
+                    self.paused = match index {
+                        0 => Paused::Resume,
+                        1 => Paused::Restart,
+                        _ => Paused::Quit,
+                    };
+                    return Ok(false);
+                }
+            },
+            Screen::Play => {
+                if let Err(e) = self.play(terminal, index) {
+                    self.error = Some(e);
+                }
+                self.reload();
+            }
+            Screen::Settings => self.change(index, 1),
+            Screen::Keys => self.binding = Some(index),
+            Screen::Scores => {}
+            Screen::Replays => {
+                if let Some(replay) = self.replays.get(index) {
+                    if let Err(e) = replay::play(terminal, replay, &self.cfg) {
+                        self.error = Some(e);
+                    }
+                }
+            }
+        }
+        Ok(true)
+    }
+
+    fn play(&mut self, terminal: &mut ratatui::DefaultTerminal, index: usize) -> Result<(), String> {
+        let Some(game) = self.games.get(index).cloned() else {
+            return Ok(());
+        };
+        let settings = &mut self.cfg.menu.game;
+        match &game {
+            Game::Mode(mode) => {
+                settings.mode = *mode;
+                settings.rules = None;
+            }
+            Game::Rules(path, _) => settings.rules = Some(path.clone()),
+            _ => {}
+        }
+        self.cfg.menu.last_played = game.label();
+        self.save();
+
+        let cfg = self.cfg.clone();
+        let menu = &cfg.menu;
+        let mut publisher = self.publish.as_deref().map(Publisher::bind).transpose()?;
+        let versus_rules = || crate::load_rules(None, menu.game.pieces.as_deref(), RuleSet::versus());
+
+        match game {
+            Game::Mode(_) | Game::Rules(..) => {
+                let (grid, place) = crate::run(terminal, &menu.game, &cfg, &self.config_file, &mut publisher)?;
+                let mut message = summary(&grid).into_iter().map(Line::from).collect::<Vec<Line>>();
+                if let Some(place) = place {
+                    message.push(Line::from(format!("New high score: #{}", place)).fg(Color::Yellow));
+                }
+                message.push(Line::from(""));
+                message.push(Line::from("any key: back to the menu"));
+                terminal
+                    .draw(|frame| {
+                        ui::draw(frame, grid.clone(), &cfg);
+                        ui::draw_message(frame, message);
+                    })
+                    .map_err(|e| e.to_string())?;
+                wait_for_key()?;
+                Ok(())
+            }
+            Game::Puzzles => puzzle::play(terminal, Path::new(PUZZLES_DIR), cfg.clone(), &self.config_file),
+            Game::Drills => drill::play(terminal, drill::Drill::built_in(), cfg.clone(), &self.config_file),
+            Game::Versus => versus::play(terminal, versus_rules()?, cfg.clone(), &self.config_file, None, publisher),
+            Game::Bot => versus::play(terminal, versus_rules()?, cfg.clone(), &self.config_file, Some(menu.difficulty.settings()), publisher),
+            Game::Royale => royale::play(
+                terminal,
+                versus_rules()?,
+                cfg.clone(),
+                &self.config_file,
+                Field {
+                    bots: menu.bots,
+                    bot: menu.difficulty.settings(),
+                    targeting: Targeting::Random,
+                },
+                publisher,
+            ),
+        }
+    }
+
+    fn setting(&self, index: usize) -> String {
+        let menu = &self.cfg.menu;
+        match index {
+            0 => menu.game.level.to_string(),
+            1 => match menu.game.visibility {
+                Visibility::Visible => "Visible".to_string(),
+                Visibility::Fade(seconds) => format!("Fade after {}s", seconds),
+                Visibility::Invisible => "Invisible".to_string(),
+            },
+            2 => if menu.game.big { "On" } else { "Off" }.to_string(),
+            3 => menu.game.pieces.as_deref().map_or("Tetrominoes".to_string(), file_name),
+            4 => self.cfg.theme.name.clone(),
+            5 => self.cfg.theme.glyphs.name().to_string(),
+            6 => self.cfg.theme.empty.name().to_string(),
+            7 => if self.cfg.hide_help { "Off" } else { "On" }.to_string(),
+            8 => if self.cfg.hide_stats { "Off" } else { "On" }.to_string(),
+            9 => if self.cfg.no_animations { "Off" } else { "On" }.to_string(),
+            10 => format!("{:?}", menu.difficulty),
+            11 => menu.bots.to_string(),
+            _ => format!("{} ms per cell", self.cfg.soft_drop_ms_per_cell),
+        }
+    }
+
+    /// steps the setting at `index` forwards or backwards, wrapping around at the ends
+    fn change(&mut self, index: usize, step: i32) {
+        let menu = &mut self.cfg.menu;
+        match index {
+            0 => menu.game.level = cycle(menu.game.level as usize - 1, MAX_START_LEVEL as usize, step) as u32 + 1,
+            1 => {
+                let current = VISIBILITIES.iter().position(|v| *v == menu.game.visibility).unwrap_or(0);
+                menu.game.visibility = VISIBILITIES[cycle(current, VISIBILITIES.len(), step)];
+            }
+            2 => menu.game.big = !menu.game.big,
+            3 => {
+                let mut sets = vec![None];
+                sets.extend(json_files(PIECES_DIR).into_iter().map(Some));
+                let current = sets.iter().position(|set| *set == menu.game.pieces).unwrap_or(0);
+                menu.game.pieces = sets[cycle(current, sets.len(), step)].clone();
+            }
+            4 => {
+                let current = PRESETS.iter().position(|name| *name == self.cfg.theme.name).unwrap_or(0);
+                let name = PRESETS[cycle(current, PRESETS.len(), step)];
+                self.cfg.theme = Theme::preset(name).unwrap();
+            }
+            5 => {
+                let theme = &mut self.cfg.theme;
+                let current = Glyphs::ALL.iter().position(|glyphs| *glyphs == theme.glyphs).unwrap_or(0);
+                theme.glyphs = Glyphs::ALL[cycle(current, Glyphs::ALL.len(), step)];
+            }
+            6 => {
+                let theme = &mut self.cfg.theme;
+                let current = EmptyCells::ALL.iter().position(|empty| *empty == theme.empty).unwrap_or(0);
+                theme.empty = EmptyCells::ALL[cycle(current, EmptyCells::ALL.len(), step)];
+            }
+            7 => self.cfg.hide_help = !self.cfg.hide_help,
+            8 => self.cfg.hide_stats = !self.cfg.hide_stats,
+            9 => self.cfg.no_animations = !self.cfg.no_animations,
+            10 => {
+                let current = DIFFICULTIES.iter().position(|d| *d == menu.difficulty).unwrap_or(0);
+                menu.difficulty = DIFFICULTIES[cycle(current, DIFFICULTIES.len(), step)];
+            }
+            11 => menu.bots = cycle(menu.bots.clamp(1, MAX_BOTS) - 1, MAX_BOTS, step) + 1,
+            12 => self.cfg.soft_drop_ms_per_cell = self.cfg.soft_drop_ms_per_cell.saturating_add_signed(step as i8 * 5),
+            _ => return,
+        }
+        self.save();
+    }
+
+    /// picks up the settings changed in the pause menu during a game
+    fn reload(&mut self) {
+        let file = std::fs::read_to_string(&self.config_file);
+        if let Some(cfg) = file.ok().and_then(|file| serde_json::from_str(&file).ok()) {
+            self.cfg = cfg;
+        }
+    }
+
+    fn save(&mut self) {
+        let serialized = serde_json::to_string(&self.cfg).unwrap();
+        if let Err(e) = std::fs::write(&self.config_file, serialized) {
+            self.error = Some(format!("{}: {}", self.config_file, e));
+        }
+    }
+}
+
+/// the play menu: the built-in modes, every rule set in `rules/` and the other ways to play
+fn games() -> Vec<Game> {
+    let mut games = [Mode::Marathon, Mode::Zen, Mode::Master, Mode::Finesse].map(Game::Mode).into_iter().collect::<Vec<Game>>();
+    for path in json_files(RULES_DIR) {
+        if let Ok(rules) = RuleSet::load(&path) {
+            games.push(Game::Rules(path, rules.name));
+        }
+    }
+    if Path::new(PUZZLES_DIR).is_dir() {
+        games.push(Game::Puzzles);
+    }
+    games.extend([Game::Drills, Game::Versus, Game::Bot, Game::Royale]);
+    games
+}
+
+fn json_files(dir: &str) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect::<Vec<PathBuf>>();
+    files.sort();
+    files
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_stem().map_or_else(|| path.display().to_string(), |stem| stem.to_string_lossy().to_string())
+}
+
+fn cycle(current: usize, len: usize, step: i32) -> usize {
+    (current as i64 + step as i64).rem_euclid(len as i64) as usize
+}
+
+/// the box the menu is drawn in, in the middle of the screen
+fn panel(area: Rect, items: usize) -> Rect {
+    let width = PANEL_WIDTH.min(area.width);
+    // the items, a blank line and the hint, inside the border
+    let height = (items as u16 + 4).min(area.height);
+    Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    )
+}
+
+fn item_at(panel: Rect, column: u16, row: u16, items: usize) -> Option<usize> {
+    if column <= panel.x || column + 1 >= panel.x + panel.width || row <= panel.y {
+        return None;
+    }
+    let index = (row - panel.y - 1) as usize;
+    (index < items).then_some(index)
+}
+
+fn binding(cfg: &Config, index: usize) -> &KeyCodeWrapper {
+    [
+        &cfg.move_left,
+        &cfg.move_right,
+        &cfg.hard_drop,
+        &cfg.soft_drop,
+        &cfg.rotate_cw,
+        &cfg.rotate_ccw,
+        &cfg.hold,
+        &cfg.pause,
+        &cfg.quit,
+    ][index]
+}
+
+fn binding_mut(cfg: &mut Config, index: usize) -> &mut KeyCodeWrapper {
+    match index {
+        0 => &mut cfg.move_left,
+        1 => &mut cfg.move_right,
+        2 => &mut cfg.hard_drop,
+        3 => &mut cfg.soft_drop,
+        4 => &mut cfg.rotate_cw,
+        5 => &mut cfg.rotate_ccw,
+        6 => &mut cfg.hold,
+        7 => &mut cfg.pause,
+        _ => &mut cfg.quit,
+    }
+}
@@ -0,0 +1,98 @@
+// src/mode.rs
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::speed::FRAMES_PER_SECOND;
+
+/// how long the whole stack stays visible after a line clear
+pub const REVEAL_FRAMES: u64 = 60;
+
+/// number of rows wiped from the top of the board when a zen game would otherwise top out
+pub const ZEN_CLEAR_ROWS: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+pub enum Mode {
+    /// Classic game with gravity and a game over when the stack reaches the top
+    Marathon,
+    /// No gravity and no top-out, for practising stacking without time pressure
+    Zen,
+    /// TGM-style levels 0-999 with 20G gravity and a grade at the end
+    Master,
+    /// No gravity, flags every piece placed with more key presses than needed
+    Finesse,
+    /// Preset board and queue, started through the `puzzle` subcommand
+    #[value(skip)]
+    Puzzle,
+    /// Build a preset setup piece by piece, started through the `drill` subcommand
+    #[value(skip)]
+    Drill,
+    /// Rules loaded from a file with `--rules`
+    #[value(skip)]
+    Custom,
+    /// Two players side by side sending each other garbage, started through the `versus` subcommand
+    #[value(skip)]
+    Versus,
+    /// One player against a crowd of bots, started through the `royale` subcommand
+    #[value(skip)]
+    Royale,
+}
+
+impl Mode {
+    /// whether pieces can be placed with the mouse, in the modes that wait for them to be placed
+    pub fn mouse_placement(&self) -> bool {
+        matches!(self, Mode::Zen | Mode::Puzzle | Mode::Drill)
+    }
+
+    /// whether pieces fall on their own between inputs
+    pub fn has_gravity(&self) -> bool {
+        !matches!(self, Mode::Zen | Mode::Finesse | Mode::Puzzle | Mode::Drill)
+    }
+
+    /// whether reaching the top of the board ends the game
+    pub fn can_top_out(&self) -> bool {
+        !matches!(self, Mode::Zen | Mode::Finesse)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Mode::Marathon => "Marathon",
+            Mode::Zen => "Zen",
+            Mode::Master => "Master",
+            Mode::Finesse => "Finesse",
+            Mode::Puzzle => "Puzzle",
+            Mode::Drill => "Drill",
+            Mode::Custom => "Custom",
+            Mode::Versus => "Versus",
+            Mode::Royale => "Royale",
+        }
+    }
+}
+
+/// modifier that hides locked blocks, can be combined with any mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Visibility {
+    Visible,
+    /// locked blocks disappear after this many seconds
+    Fade(u32),
+    Invisible,
+}
+
+impl Visibility {
+    /// whether a block locked on `locked_at` should be hidden on frame `frames`
+    pub fn hides(&self, locked_at: u64, frames: u64) -> bool {
+        match self {
+            Visibility::Visible => false,
+            Visibility::Fade(seconds) => frames.saturating_sub(locked_at) >= *seconds as u64 * FRAMES_PER_SECOND as u64,
+            Visibility::Invisible => true,
+        }
+    }
+
+    pub fn prefix(&self) -> &'static str {
+        match self {
+            Visibility::Visible => "",
+            Visibility::Fade(_) => "Fading ",
+            Visibility::Invisible => "Invisible ",
+        }
+    }
+}
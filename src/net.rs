@@ -0,0 +1,672 @@
+// src/net.rs
+//
+// Networked versus keeps both games in deterministic lockstep. Each side simulates both boards from
+// the same seed and rule set, so only the players' inputs have to cross the wire. Inputs pressed
+// before frame `f` is simulated are scheduled for frame `f + INPUT_DELAY` and sent straight away, and
+// a frame is only simulated once the inputs of both players for it are in. A checksum of both boards
+// is swapped every second to catch the games drifting apart. Pausing tells the other side, and
+// neither side simulates or shows the boards until whoever paused resumes.
+//
+// Every message is a tag byte followed by big-endian fields:
+//
+//     1 Hello     "TTRS", u16 protocol version
+//     2 Start     u64 seed, u32 length, rule set as JSON     (host to joiner only)
+//     3 Inputs    u32 frame, u8 count, one byte per action
+//     4 Checksum  u32 frame, u64 hash
+//     5 Quit
+//     6 Pause
+//     7 Resume
+
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::time::{Duration, Instant};
+
+use crossterm::event::{self, Event, KeyEventKind};
+use ratatui::text::Line;
+
+use crate::block::Block;
+use crate::config::Config;
+use crate::grid::Grid;
+use crate::mode::Mode;
+use crate::rules::RuleSet;
+use crate::spectate::Publisher;
+use crate::speed::FRAMES_PER_SECOND;
+use crate::ui;
+use crate::utils::{countdown, wait_for_key};
+use crate::versus::{self, exchange_garbage, Action};
+
+/// bumped whenever the messages or the simulation change in a way that would desync older builds
+pub const PROTOCOL_VERSION: u16 = 2;
+const MAGIC: &[u8; 4] = b"TTRS";
+/// the longest rule set the host may send, far more than any real one needs
+const MAX_RULES_LEN: u32 = 64 * 1024;
+/// frames between a key press and the frame it's applied on, giving it time to reach the opponent
+const INPUT_DELAY: u32 = 3;
+const CHECKSUM_FRAMES: u32 = FRAMES_PER_SECOND;
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+/// how long to wait on the opponent's inputs before saying the game is stalled
+const LAG_WARNING: Duration = Duration::from_millis(500);
+/// frames the simulation may fall behind the clock before it stops trying to catch up
+const MAX_CATCH_UP: u32 = 10;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Message {
+    Hello { version: u16 },
+    Start { seed: u64, rules: String },
+    Inputs { frame: u32, actions: Vec<Action> },
+    Checksum { frame: u32, hash: u64 },
+    Quit,
+    Pause,
+    Resume,
+}
+
+impl Message {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        match self {
+            Message::Hello { version } => {
+                bytes.push(1);
+                bytes.extend(MAGIC);
+                bytes.extend(version.to_be_bytes());
+            }
+            Message::Start { seed, rules } => {
+                bytes.push(2);
+                bytes.extend(seed.to_be_bytes());
+                bytes.extend((rules.len() as u32).to_be_bytes());
+                bytes.extend(rules.as_bytes());
+            }
+            Message::Inputs { frame, actions } => {
+                bytes.push(3);
+                bytes.extend(frame.to_be_bytes());
+                bytes.push(actions.len() as u8);
+                bytes.extend(actions.iter().map(|action| *action as u8));
+            }
+            Message::Checksum { frame, hash } => {
+                bytes.push(4);
+                bytes.extend(frame.to_be_bytes());
+                bytes.extend(hash.to_be_bytes());
+            }
+            Message::Quit => bytes.push(5),
+            Message::Pause => bytes.push(6),
+            Message::Resume => bytes.push(7),
+        }
+        bytes
+    }
+
+    pub fn read(reader: &mut impl Read) -> io::Result<Message> {
+        let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+
+        match read_array::<1>(reader)?[0] {
+            1 => {
+                if &read_array::<4>(reader)? != MAGIC {
+                    return Err(invalid("the other side isn't a tetris game"));
+                }
+                let version = u16::from_be_bytes(read_array(reader)?);
+                Ok(Message::Hello { version })
+            }
+            2 => {
+                let seed = u64::from_be_bytes(read_array(reader)?);
+                let len = u32::from_be_bytes(read_array(reader)?);
+                if len > MAX_RULES_LEN {
+                    return Err(invalid("rule set is too long"));
+                }
+                let mut rules = vec![0; len as usize];
+                reader.read_exact(&mut rules)?;
+                let rules = String::from_utf8(rules).map_err(|_| invalid("rule set isn't valid UTF-8"))?;
+                Ok(Message::Start { seed, rules })
+            }
+            3 => {
+                let frame = u32::from_be_bytes(read_array(reader)?);
+                let [count] = read_array::<1>(reader)?;
+                let mut actions = vec![0; count as usize];
+                reader.read_exact(&mut actions)?;
+                let actions = actions
+                    .iter()
+                    .map(|byte| Action::ALL.get(*byte as usize).copied().ok_or_else(|| invalid("unknown action")))
+                    .collect::<io::Result<Vec<Action>>>()?;
+                Ok(Message::Inputs { frame, actions })
+            }
+            4 => {
+                let frame = u32::from_be_bytes(read_array(reader)?);
+                let hash = u64::from_be_bytes(read_array(reader)?);
+                Ok(Message::Checksum { frame, hash })
+            }
+            5 => Ok(Message::Quit),
+            6 => Ok(Message::Pause),
+            7 => Ok(Message::Resume),
+            _ => Err(invalid("unknown message")),
+        }
+    }
+}
+
+fn read_array<const N: usize>(reader: &mut impl Read) -> io::Result<[u8; N]> {
+    let mut bytes = [0; N];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// one side of a networked match, with both boards simulated in lockstep
+pub struct Session {
+    stream: TcpStream,
+    messages: Receiver<io::Result<Message>>,
+    /// 0 for the host, 1 for the player who joined
+    pub me: usize,
+    pub grids: [Grid; 2],
+    pub topped_out: [bool; 2],
+    /// the next frame to simulate
+    pub frame: u32,
+    pub opponent_quit: bool,
+    /// whether each player has the game paused
+    pub paused: [bool; 2],
+    /// inputs of each player by the frame they apply on
+    inputs: [HashMap<u32, Vec<Action>>; 2],
+    /// pressed since the last simulated frame, not sent yet
+    pending: Vec<Action>,
+    checksums: [HashMap<u32, u64>; 2],
+}
+
+impl Session {
+    pub fn host(mut stream: TcpStream, mut rules: RuleSet) -> Result<Session, String> {
+        handshake(&mut stream)?;
+        let seed = rules.seed.unwrap_or_else(rand::random);
+        rules.seed = Some(seed);
+        let json = serde_json::to_string(&rules).map_err(|e| e.to_string())?;
+        send(&mut stream, &Message::Start { seed, rules: json })?;
+        Session::new(stream, 0, rules)
+    }
+
+    pub fn join(mut stream: TcpStream) -> Result<Session, String> {
+        handshake(&mut stream)?;
+        match Message::read(&mut stream).map_err(|e| e.to_string())? {
+            Message::Start { seed, rules } => {
+                let mut rules: RuleSet = serde_json::from_str(&rules).map_err(|e| e.to_string())?;
+                rules.validate(1).map_err(|e| format!("the host's rule set: {}", e))?;
+                rules.seed = Some(seed);
+                Session::new(stream, 1, rules)
+            }
+            message => Err(format!("expected the game to start, got {:?}", message)),
+        }
+    }
+
+    fn new(stream: TcpStream, me: usize, rules: RuleSet) -> Result<Session, String> {
+        stream.set_read_timeout(None).map_err(|e| e.to_string())?;
+        stream.set_nodelay(true).map_err(|e| e.to_string())?;
+
+        let mut reader = stream.try_clone().map_err(|e| e.to_string())?;
+        let (tx, messages) = mpsc::channel();
+        std::thread::spawn(move || loop {
+            let message = Message::read(&mut reader);
+            let failed = message.is_err();
+            if tx.send(message).is_err() || failed {
+                break;
+            }
+        });
+
+        let mut grids = [Grid::with_rules(rules.clone(), 1), Grid::with_rules(rules, 1)];
+        for grid in grids.iter_mut() {
+            grid.mode = Mode::Versus;
+            grid.next(None);
+        }
+
+        // nobody can have pressed anything for the first few frames
+        let mut inputs = [HashMap::new(), HashMap::new()];
+        for frame in 0..INPUT_DELAY {
+            inputs[0].insert(frame, Vec::new());
+            inputs[1].insert(frame, Vec::new());
+        }
+
+        Ok(Session {
+            stream,
+            messages,
+            me,
+            grids,
+            topped_out: [false, false],
+            frame: 0,
+            opponent_quit: false,
+            paused: [false, false],
+            inputs,
+            pending: Vec::new(),
+            checksums: [HashMap::new(), HashMap::new()],
+        })
+    }
+
+    pub fn press(&mut self, action: Action) {
+        self.pending.push(action);
+    }
+
+    /// pauses or resumes the game for both players
+    pub fn pause(&mut self, paused: bool) -> Result<(), String> {
+        self.paused[self.me] = paused;
+        send(&mut self.stream, if paused { &Message::Pause } else { &Message::Resume })
+    }
+
+    pub fn quit(&mut self) {
+        let _ = send(&mut self.stream, &Message::Quit);
+    }
+
+    /// takes in everything the opponent has sent so far
+    pub fn receive(&mut self) -> Result<(), String> {
+        let them = 1 - self.me;
+        loop {
+            match self.messages.try_recv() {
+                Ok(Ok(Message::Inputs { frame, actions })) => {
+                    self.inputs[them].insert(frame, actions);
+                }
+                Ok(Ok(Message::Checksum { frame, hash })) => {
+                    self.checksums[them].insert(frame, hash);
+                }
+                Ok(Ok(Message::Pause)) => self.paused[them] = true,
+                Ok(Ok(Message::Resume)) => self.paused[them] = false,
+                Ok(Ok(Message::Quit)) => {
+                    self.opponent_quit = true;
+                    return Ok(());
+                }
+                Ok(Ok(message)) => return Err(format!("unexpected message from the opponent: {:?}", message)),
+                Ok(Err(e)) => return Err(format!("lost the connection: {}", e)),
+                Err(TryRecvError::Empty) => return Ok(()),
+                Err(TryRecvError::Disconnected) => return Err("lost the connection".to_string()),
+            }
+        }
+    }
+
+    /// simulates the next frame if both players' inputs for it are in, returns false if it's still waiting
+    pub fn step(&mut self) -> Result<bool, String> {
+        let (me, frame) = (self.me, self.frame);
+        if !self.inputs[1 - me].contains_key(&frame) {
+            return Ok(false);
+        }
+
+        let actions = std::mem::take(&mut self.pending);
+        send(
+            &mut self.stream,
+            &Message::Inputs {
+                frame: frame + INPUT_DELAY,
+                actions: actions.clone(),
+            },
+        )?;
+        self.inputs[me].insert(frame + INPUT_DELAY, actions);
+
+        // both sides go through the players in the same order so they stay in sync
+        for player in 0..2 {
+            let topped_out = &mut self.topped_out[player];
+            for action in self.inputs[player].remove(&frame).unwrap_or_default() {
+                action.apply(&mut self.grids[player], |_| *topped_out = true);
+            }
+            self.grids[player].tick(|_| *topped_out = true);
+        }
+        exchange_garbage(&mut self.grids);
+        self.frame += 1;
+
+        if frame.is_multiple_of(CHECKSUM_FRAMES) {
+            let hash = self.checksum();
+            self.checksums[me].insert(frame, hash);
+            send(&mut self.stream, &Message::Checksum { frame, hash })?;
+        }
+        self.check_sync()?;
+
+        Ok(true)
+    }
+
+    /// FNV-1a over both boards, spelled out so every build works it out the same way
+    fn checksum(&self) -> u64 {
+        let mut bytes = Vec::new();
+        for grid in &self.grids {
+            for row in &grid.rows {
+                for block in &row.cells {
+                    let (tag, shape) = match block {
+                        Block::Full(shape) => (1, shape.to_char() as u32),
+                        Block::Empty => (2, 0),
+                        Block::Active(shape) => (3, shape.to_char() as u32),
+                        Block::Garbage => (4, 0),
+                    };
+                    bytes.push(tag);
+                    bytes.extend(shape.to_be_bytes());
+                }
+            }
+            bytes.extend(grid.score.to_be_bytes());
+            bytes.extend(grid.garbage.incoming.iter().flat_map(|lines| lines.to_be_bytes()));
+        }
+        fnv(&bytes)
+    }
+
+    /// compares the checksums both sides have worked out so far
+    fn check_sync(&mut self) -> Result<(), String> {
+        let them = 1 - self.me;
+        let frames = self.checksums[them]
+            .keys()
+            .filter(|frame| self.checksums[self.me].contains_key(frame))
+            .copied()
+            .collect::<Vec<u32>>();
+
+        for frame in frames {
+            if self.checksums[them].remove(&frame) != self.checksums[self.me].remove(&frame) {
+                return Err(format!("the games went out of sync on frame {}", frame));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn fnv(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| (hash ^ *byte as u64).wrapping_mul(0x100000001b3))
+}
+
+fn send(stream: &mut TcpStream, message: &Message) -> Result<(), String> {
+    stream
+        .write_all(&message.encode())
+        .map_err(|e| format!("lost the connection: {}", e))
+}
+
+/// swaps protocol versions, both sides have to be running the same one
+fn handshake(stream: &mut TcpStream) -> Result<(), String> {
+    stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT)).map_err(|e| e.to_string())?;
+    send(stream, &Message::Hello { version: PROTOCOL_VERSION })?;
+
+    match Message::read(stream).map_err(|e| e.to_string())? {
+        Message::Hello { version } if version == PROTOCOL_VERSION => Ok(()),
+        Message::Hello { version } => Err(format!(
+            "the opponent is on protocol version {}, this game is on version {}",
+            version, PROTOCOL_VERSION
+        )),
+        message => Err(format!("expected a hello, got {:?}", message)),
+    }
+}
+
+/// waits for someone to join on `port`, then plays them
+pub fn host(
+    terminal: &mut ratatui::DefaultTerminal,
+    port: u16,
+    rules: RuleSet,
+    cfg: Config,
+    publisher: Option<Publisher>,
+) -> Result<(), String> {
+    let listener = TcpListener::bind(("0.0.0.0", port)).map_err(|e| format!("port {}: {}", port, e))?;
+    listener.set_nonblocking(true).map_err(|e| e.to_string())?;
+
+    let stream = loop {
+        let message = vec![
+            Line::from(format!("Waiting for an opponent on port {}", port)),
+            Line::from(""),
+            Line::from("quit key: cancel"),
+        ];
+        terminal
+            .draw(|frame| ui::draw_message(frame, message))
+            .map_err(|e| e.to_string())?;
+
+        match listener.accept() {
+            Ok((stream, _)) => break stream,
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => {}
+            Err(e) => return Err(e.to_string()),
+        }
+        if event::poll(Duration::from_millis(100)).unwrap() {
+            if let Ok(Event::Key(key)) = event::read() {
+                if key.kind == KeyEventKind::Press && key.code == cfg.quit.code {
+                    return Ok(());
+                }
+            }
+        }
+    };
+    stream.set_nonblocking(false).map_err(|e| e.to_string())?;
+
+    let session = Session::host(stream, rules)?;
+    play(terminal, session, cfg, publisher)
+}
+
+/// connects to a game hosted at `addr`, the host's rule set is used
+pub fn join(
+    terminal: &mut ratatui::DefaultTerminal,
+    addr: &str,
+    cfg: Config,
+    publisher: Option<Publisher>,
+) -> Result<(), String> {
+    terminal
+        .draw(|frame| ui::draw_message(frame, vec![Line::from(format!("Connecting to {}", addr))]))
+        .map_err(|e| e.to_string())?;
+
+    let stream = TcpStream::connect(addr).map_err(|e| format!("{}: {}", addr, e))?;
+    let session = Session::join(stream)?;
+    play(terminal, session, cfg, publisher)
+}
+
+fn play(
+    terminal: &mut ratatui::DefaultTerminal,
+    mut session: Session,
+    cfg: Config,
+    mut publisher: Option<Publisher>,
+) -> Result<(), String> {
+    for grid in session.grids.iter_mut() {
+        grid.finesse.judged = cfg.shows_faults();
+    }
+    let frame_time = Duration::from_secs(1) / FRAMES_PER_SECOND;
+    let mut last_frame = Instant::now();
+    let mut waiting_since: Option<Instant> = None;
+    let mut titles = [String::new(), String::new()];
+    titles[session.me] = "You".to_string();
+    titles[1 - session.me] = "Opponent".to_string();
+
+    let ending = loop {
+        let lagging = waiting_since.is_some_and(|since| since.elapsed() >= LAG_WARNING);
+        let paused = session.paused.contains(&true);
+        terminal
+            .draw(|frame| {
+                // the boards stay hidden while the game is paused, the same as in single player
+                if session.paused[session.me] {
+                    let message = ["Paused", "", "pause key: resume", "quit key: leave the game"];
+                    ui::draw_message(frame, message.map(Line::from).to_vec());
+                } else if paused {
+                    ui::draw_message(frame, vec![Line::from("The opponent paused the game")]);
+                } else {
+                    versus::draw(frame, &session.grids, titles.clone(), &keys(&session, &cfg));
+                }
+                if lagging && !paused {
+                    ui::draw_message(frame, vec![Line::from("Waiting for the opponent...")]);
+                }
+            })
+            .map_err(|e| e.to_string())?;
+        if let Some(publisher) = &mut publisher {
+            publisher.publish(&[&session.grids[0], &session.grids[1]]);
+        }
+
+        if let Err(e) = session.receive() {
+            break e;
+        }
+        if session.opponent_quit {
+            break "The opponent left the game".to_string();
+        }
+
+        if paused {
+            waiting_since = None;
+        }
+        // stop on the frame someone topped out, so both players topping out together is a draw
+        while last_frame.elapsed() >= frame_time && !paused && !session.topped_out.contains(&true) {
+            match session.step() {
+                Ok(true) => {
+                    waiting_since = None;
+                    last_frame += frame_time;
+                }
+                Ok(false) => {
+                    waiting_since.get_or_insert_with(Instant::now);
+                    break;
+                }
+                Err(e) => return finish(terminal, &session, &cfg, titles, e),
+            }
+        }
+        // after a stall, carry on from now instead of rushing through the missed frames
+        if last_frame.elapsed() > frame_time * MAX_CATCH_UP {
+            last_frame = Instant::now();
+        }
+
+        while event::poll(Duration::from_millis(1)).unwrap() {
+            match event::read() {
+                Ok(Event::Key(key)) if key.kind == KeyEventKind::Press => {
+                    if key.code == cfg.quit.code {
+                        session.quit();
+                        return Ok(());
+                    }
+                    if key.code == cfg.pause.code && session.paused[session.me] {
+                        countdown(terminal, |frame| versus::draw(frame, &session.grids, titles.clone(), &keys(&session, &cfg)))?;
+                        if let Err(e) = session.pause(false) {
+                            return finish(terminal, &session, &cfg, titles, e);
+                        }
+                        last_frame = Instant::now();
+                    } else if key.code == cfg.pause.code {
+                        if let Err(e) = session.pause(true) {
+                            return finish(terminal, &session, &cfg, titles, e);
+                        }
+                    } else if let Some(action) = Action::from_key(&cfg, key.code) {
+                        // nothing moves while the game is paused
+                        if !session.paused.contains(&true) {
+                            session.press(action);
+                        }
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => return Err(e.to_string()),
+            }
+        }
+
+        match session.topped_out {
+            [true, true] => break "Draw".to_string(),
+            [false, false] => {}
+            topped_out => break if topped_out[session.me] { "You lose" } else { "You win!" }.to_string(),
+        }
+    };
+
+    finish(terminal, &session, &cfg, titles, ending)
+}
+
+/// this player's keys are shown next to their own board only
+fn keys(session: &Session, cfg: &Config) -> [Config; 2] {
+    let mut keys = [cfg.without_help(), cfg.without_help()];
+    keys[session.me] = cfg.clone();
+    keys
+}
+
+/// shows how the match ended over the final boards
+fn finish(terminal: &mut ratatui::DefaultTerminal, session: &Session, cfg: &Config, titles: [String; 2], ending: String) -> Result<(), String> {
+    let message = vec![Line::from(ending), Line::from(""), Line::from("any key: quit")];
+    terminal
+        .draw(|frame| {
+            versus::draw(frame, &session.grids, titles.clone(), &keys(session, cfg));
+            ui::draw_message(frame, message.clone());
+        })
+        .map_err(|e| e.to_string())?;
+    wait_for_key()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Barrier};
+
+    use super::*;
+
+    #[test]
+    fn messages_round_trip() {
+        let messages = [
+            Message::Hello { version: PROTOCOL_VERSION },
+            Message::Start { seed: 42, rules: "{\"width\":8}".to_string() },
+            Message::Inputs { frame: 7, actions: Action::ALL.to_vec() },
+            Message::Inputs { frame: 8, actions: Vec::new() },
+            Message::Checksum { frame: 60, hash: u64::MAX },
+            Message::Quit,
+            Message::Pause,
+            Message::Resume,
+        ];
+        for message in messages {
+            assert_eq!(Message::read(&mut message.encode().as_slice()).unwrap(), message);
+        }
+    }
+
+    #[test]
+    fn long_rule_sets_are_refused() {
+        let mut bytes = vec![2];
+        bytes.extend(0u64.to_be_bytes());
+        bytes.extend(u32::MAX.to_be_bytes());
+        assert!(Message::read(&mut bytes.as_slice()).is_err());
+    }
+
+    /// plays `frames` frames pressing `actions` on the first few, then waits at `done` so neither
+    /// side hangs up while the other still needs its inputs. returns the final checksum
+    fn play_frames(mut session: Session, actions: &[Action], frames: u32, done: &Barrier) -> u64 {
+        for action in actions {
+            session.press(*action);
+        }
+        while session.frame < frames {
+            session.receive().unwrap();
+            if !session.step().unwrap() {
+                std::thread::sleep(Duration::from_millis(1));
+            }
+        }
+        done.wait();
+        session.checksum()
+    }
+
+    #[test]
+    fn host_and_join_stay_in_sync() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let rules = RuleSet { seed: Some(7), ..RuleSet::default() };
+        let done = Arc::new(Barrier::new(2));
+
+        let host_done = done.clone();
+        let host = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let session = Session::host(stream, rules).unwrap();
+            play_frames(session, &[Action::Left, Action::HardDrop], 20, &host_done)
+        });
+        let session = Session::join(TcpStream::connect(addr).unwrap()).unwrap();
+        assert_eq!(session.me, 1);
+        assert_eq!(session.grids[0].rules.seed, Some(7));
+        let joined = play_frames(session, &[Action::RotateCw, Action::Right, Action::HardDrop], 20, &done);
+
+        assert_eq!(host.join().unwrap(), joined);
+    }
+
+    #[test]
+    fn pausing_reaches_the_opponent() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let done = Arc::new(Barrier::new(2));
+
+        let host_done = done.clone();
+        let host = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut session = Session::host(stream, RuleSet::default()).unwrap();
+            session.pause(true).unwrap();
+            host_done.wait();
+            session.pause(false).unwrap();
+            host_done.wait();
+        });
+        let mut session = Session::join(TcpStream::connect(addr).unwrap()).unwrap();
+        let wait_for = |session: &mut Session, paused: bool| {
+            let start = Instant::now();
+            while session.paused[0] != paused && start.elapsed() < HANDSHAKE_TIMEOUT {
+                session.receive().unwrap();
+            }
+            assert_eq!(session.paused, [paused, false]);
+        };
+
+        wait_for(&mut session, true);
+        done.wait();
+        wait_for(&mut session, false);
+        done.wait();
+        host.join().unwrap();
+    }
+
+    #[test]
+    fn joining_checks_the_rule_set() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let host = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            handshake(&mut stream).unwrap();
+            let rules = "{\"width\":0}".to_string();
+            send(&mut stream, &Message::Start { seed: 1, rules }).unwrap();
+        });
+        assert!(Session::join(TcpStream::connect(addr).unwrap()).is_err());
+        host.join().unwrap();
+    }
+}
@@ -0,0 +1,339 @@
+// src/puzzle.rs
+//
+// Puzzles are JSON files with a starting board, a fixed queue and a goal, e.g.
+//
+//     {
+//         "name": "Tuck it in",
+//         "rows": ["##.#######", "#..#######"],
+//         "queue": ["T", "I"],
+//         "hold": "O",
+//         "goal": { "lines": 2 }
+//     }
+//
+// `rows` are listed from top to bottom and any missing rows at the top are left empty. `.` or a
+// space is an empty cell, a piece letter is a block of that piece and anything else is garbage.
+
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crossterm::event::{self, KeyCode};
+use ratatui::text::Line;
+use serde::Deserialize;
+
+use crate::block::Block;
+use crate::config::Config;
+use crate::grid::Grid;
+use crate::menu::Paused;
+use crate::mode::Mode;
+use crate::shape::Shape;
+use crate::speed::FRAMES_PER_SECOND;
+use crate::ui;
+use crate::utils::{handle_events, pause, restarting, wait_for_key, wait_for_room, Round};
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum Goal {
+    /// clear at least this many lines
+    Lines(u32),
+    PerfectClear,
+    TSpinDouble,
+    /// place every piece in the queue without topping out
+    Survive,
+}
+
+impl Goal {
+    pub fn describe(&self) -> String {
+        match self {
+            Goal::Lines(1) => "Clear 1 line".to_string(),
+            Goal::Lines(lines) => format!("Clear {} lines", lines),
+            Goal::PerfectClear => "Perfect clear".to_string(),
+            Goal::TSpinDouble => "T-spin double".to_string(),
+            Goal::Survive => "Use every piece".to_string(),
+        }
+    }
+
+    /// `Some(true)` once the goal is met, `Some(false)` once it can't be met any more
+    pub fn check(&self, grid: &Grid) -> Option<bool> {
+        let out_of_pieces = grid.active_shape.is_none() && grid.next_shapes.is_empty();
+        let met = match self {
+            Goal::Lines(lines) => grid.lines >= *lines,
+            Goal::PerfectClear => grid.last_clear.as_ref().is_some_and(|clear| clear.perfect),
+            Goal::TSpinDouble => grid.last_clear.as_ref().is_some_and(|clear| clear.t_spin && clear.lines == 2),
+            Goal::Survive => out_of_pieces,
+        };
+
+        if met {
+            Some(true)
+        } else if out_of_pieces {
+            Some(false)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Deserialize, Clone)]
+pub struct Puzzle {
+    #[serde(default)]
+    pub name: String,
+    pub rows: Vec<String>,
+    pub queue: Vec<Shape>,
+    #[serde(default)]
+    pub hold: Option<Shape>,
+    pub goal: Goal,
+}
+
+impl Puzzle {
+    pub fn load(path: &Path) -> Result<Puzzle, String> {
+        let file = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let mut puzzle: Puzzle = serde_json::from_str(&file).map_err(|e| format!("{}: {}", path.display(), e))?;
+
+        if puzzle.name.is_empty() {
+            puzzle.name = path.file_stem().unwrap_or_default().to_string_lossy().to_string();
+        }
+        Ok(puzzle)
+    }
+
+    /// a single puzzle file, or every puzzle in a directory
+    pub fn load_all(path: &Path) -> Result<Vec<Puzzle>, String> {
+        json_files(path)?.iter().map(|path| Puzzle::load(path)).collect()
+    }
+
+    /// the starting grid for this puzzle, with its board, queue and hold already in place
+    pub fn grid(&self) -> Result<Grid, String> {
+        let mut grid = preset_grid(&self.name, &self.rows, &self.queue, self.hold)?;
+        grid.mode = Mode::Puzzle;
+        Ok(grid)
+    }
+}
+
+/// `path` itself if it's a file, or every `.json` file in it in name order if it's a directory
+pub fn json_files(path: &Path) -> Result<Vec<PathBuf>, String> {
+    if !path.is_dir() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+
+    let mut paths = std::fs::read_dir(path)
+        .map_err(|e| format!("{}: {}", path.display(), e))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect::<Vec<PathBuf>>();
+    paths.sort();
+
+    if paths.is_empty() {
+        return Err(format!("{}: no .json files found", path.display()));
+    }
+    Ok(paths)
+}
+
+/// the filled cells of `rows` in board coordinates, with the last row at the bottom of the board
+pub fn parse_rows(name: &str, rows: &[String], width: usize, height: usize) -> Result<Vec<(usize, usize, char)>, String> {
+    if rows.len() > height {
+        return Err(format!("{}: more than {} rows", name, height));
+    }
+
+    let offset = height - rows.len();
+    let mut cells = Vec::new();
+    for (y, row) in rows.iter().enumerate() {
+        if row.chars().count() > width {
+            return Err(format!("{}: row {} is wider than {} cells", name, y + 1, width));
+        }
+        for (x, c) in row.chars().enumerate() {
+            if c != '.' && c != ' ' {
+                cells.push((x, y + offset, c));
+            }
+        }
+    }
+    Ok(cells)
+}
+
+/// a grid with a preset board, fixed queue and hold, shared by puzzles and drills
+pub fn preset_grid(name: &str, rows: &[String], queue: &[Shape], hold: Option<Shape>) -> Result<Grid, String> {
+    let mut grid = Grid::new();
+
+    for (x, y, c) in parse_rows(name, rows, grid.width, grid.height)? {
+        grid.set((x, y), Shape::from_char(c).map_or(Block::Garbage, Block::Full));
+    }
+
+    grid.next_shapes = VecDeque::from(queue.to_vec());
+    grid.fixed_queue = true;
+    grid.held_shape = hold;
+    Ok(grid)
+}
+
+/// plays through every puzzle at `path`, a failed puzzle can be retried before moving on
+pub fn play(terminal: &mut ratatui::DefaultTerminal, path: &Path, mut cfg: Config, config_file: &str) -> Result<(), String> {
+    let puzzles = Puzzle::load_all(path)?;
+    let mut solved_count = 0;
+    let mut index = 0;
+
+    while index < puzzles.len() {
+        let puzzle = &puzzles[index];
+        let Some((grid, solved)) = restarting(|| play_one(terminal, puzzle, &mut cfg, config_file))? else {
+            return Ok(());
+        };
+
+        let mut message = vec![
+            Line::from(puzzle.name.clone()),
+            Line::from(if solved { "Solved!" } else { "Failed" }),
+            Line::from(""),
+        ];
+        if !solved {
+            message.push(Line::from("r: retry"));
+        }
+        message.push(Line::from(if index + 1 < puzzles.len() { "any key: next puzzle" } else { "any key: finish" }));
+
+        terminal
+            .draw(|frame| {
+                ui::draw(frame, grid.clone(), &cfg);
+                ui::draw_message(frame, message.clone());
+            })
+            .map_err(|e| e.to_string())?;
+
+        match wait_for_key()? {
+            KeyCode::Char('r') if !solved => {}
+            code if code == cfg.quit.code => break,
+            _ => {
+                solved_count += solved as usize;
+                index += 1;
+            }
+        }
+    }
+
+    if puzzles.len() > 1 {
+        terminal
+            .draw(|frame| {
+                ui::draw_message(
+                    frame,
+                    vec![
+                        Line::from(format!("Solved {} of {} puzzles", solved_count, puzzles.len())),
+                        Line::from(""),
+                        Line::from("any key: quit"),
+                    ],
+                )
+            })
+            .map_err(|e| e.to_string())?;
+        wait_for_key()?;
+    }
+
+    Ok(())
+}
+
+/// returns the final grid and whether the puzzle was solved, or `None` if the quit key was pressed
+fn play_one(terminal: &mut ratatui::DefaultTerminal, puzzle: &Puzzle, cfg: &mut Config, config_file: &str) -> Result<Round<Option<(Grid, bool)>>, String> {
+    let mut grid = puzzle.grid()?;
+    grid.finesse.judged = cfg.shows_faults();
+    let topped_out = Cell::new(false);
+    let frame_time = Duration::from_secs(1) / FRAMES_PER_SECOND;
+    let mut last_frame = Instant::now();
+
+    grid.next(None);
+
+    let notes = vec![
+        Line::from(puzzle.name.clone()),
+        Line::from(format!("Goal: {}", puzzle.goal.describe())),
+    ];
+
+    loop {
+        if wait_for_room(terminal, cfg, ui::min_size(&grid, &cfg.theme), &mut last_frame)? {
+            return Ok(Round::Over(None));
+        }
+        terminal
+            .draw(|frame| {
+                ui::draw(frame, grid.clone(), cfg);
+                ui::draw_notes(frame, &grid, cfg, notes.clone());
+            })
+            .map_err(|e| e.to_string())?;
+
+        while last_frame.elapsed() >= frame_time {
+            last_frame += frame_time;
+            grid.tick(|_| topped_out.set(true));
+        }
+
+        while event::poll(Duration::from_millis(1)).unwrap() {
+            if handle_events(&mut grid, |_| topped_out.set(true), cfg, terminal, &mut Vec::new())? {
+                return Ok(Round::Over(None));
+            }
+        }
+
+        if grid.paused {
+            match pause(terminal, &mut grid, cfg, config_file, &mut last_frame)? {
+                Paused::Resume => {}
+                Paused::Restart => return Ok(Round::Restarted),
+                Paused::Quit => return Ok(Round::Over(None)),
+            }
+        }
+
+        if topped_out.get() {
+            return Ok(Round::Over(Some((grid, false))));
+        }
+        if let Some(solved) = puzzle.goal.check(&grid) {
+            return Ok(Round::Over(Some((grid, solved))));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clear::Clear;
+
+    fn grid(queue: &[char]) -> Grid {
+        let queue = queue.iter().map(|c| Shape::from_char(*c).unwrap()).collect::<Vec<Shape>>();
+        preset_grid("test", &[], &queue, None).unwrap()
+    }
+
+    fn clear(lines: u32, t_spin: bool, perfect: bool) -> Clear {
+        Clear {
+            lines,
+            rows: Vec::new(),
+            t_spin,
+            perfect,
+            points: 0,
+            back_to_back: false,
+            combo: 1,
+            attack: 0,
+        }
+    }
+
+    #[test]
+    fn lines_are_met_once_enough_are_cleared() {
+        let mut grid = grid(&['I', 'O']);
+        assert_eq!(Goal::Lines(2).check(&grid), None);
+        grid.lines = 1;
+        assert_eq!(Goal::Lines(2).check(&grid), None);
+        grid.lines = 2;
+        assert_eq!(Goal::Lines(2).check(&grid), Some(true));
+    }
+
+    #[test]
+    fn goals_fail_once_the_queue_runs_out() {
+        let mut grid = grid(&[]);
+        assert_eq!(Goal::Lines(1).check(&grid), Some(false));
+        assert_eq!(Goal::PerfectClear.check(&grid), Some(false));
+        // the last piece meeting the goal counts
+        grid.last_clear = Some(clear(4, false, true));
+        assert_eq!(Goal::PerfectClear.check(&grid), Some(true));
+    }
+
+    #[test]
+    fn only_a_t_spin_double_counts() {
+        let mut grid = grid(&['T']);
+        grid.last_clear = Some(clear(2, false, false));
+        assert_eq!(Goal::TSpinDouble.check(&grid), None);
+        grid.last_clear = Some(clear(1, true, false));
+        assert_eq!(Goal::TSpinDouble.check(&grid), None);
+        grid.last_clear = Some(clear(2, true, false));
+        assert_eq!(Goal::TSpinDouble.check(&grid), Some(true));
+    }
+
+    #[test]
+    fn surviving_means_using_every_piece() {
+        let mut grid = grid(&['T']);
+        assert_eq!(Goal::Survive.check(&grid), None);
+        grid.next_shapes.clear();
+        assert_eq!(Goal::Survive.check(&grid), Some(true));
+    }
+}
@@ -0,0 +1,51 @@
+// src/randomizer.rs
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::shape::Shape;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RandomizerKind {
+    /// every piece is picked independently
+    #[default]
+    Random,
+    /// every piece in the set is dealt in a shuffled order before any of them repeats
+    Bag,
+}
+
+/// deals the pieces for a game, the same kind and seed always deal the same sequence
+#[derive(Clone)]
+pub struct Randomizer {
+    kind: RandomizerKind,
+    shapes: Vec<Shape>,
+    rng: StdRng,
+    bag: Vec<Shape>,
+}
+
+impl Randomizer {
+    pub fn new(kind: RandomizerKind, shapes: Vec<Shape>, seed: u64) -> Self {
+        Randomizer {
+            kind,
+            shapes,
+            rng: StdRng::seed_from_u64(seed),
+            bag: Vec::new(),
+        }
+    }
+
+    pub fn next(&mut self) -> Shape {
+        match self.kind {
+            RandomizerKind::Random => self.shapes[self.rng.gen_range(0..self.shapes.len())],
+            RandomizerKind::Bag => {
+                if self.bag.is_empty() {
+                    self.bag = self.shapes.clone();
+                    self.bag.shuffle(&mut self.rng);
+                }
+                self.bag.pop().unwrap()
+            }
+        }
+    }
+}
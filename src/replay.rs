@@ -0,0 +1,152 @@
+// src/replay.rs
+//
+// Every single player game is saved to `replays/` with the settings and rules it was played with,
+// the seed included, and each input along with the frame it was pressed on. A game plays the same
+// way every time it gets the same inputs on the same frames, so a replay is played back by feeding
+// the inputs to a fresh board.
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crossterm::event::{self, Event, KeyEventKind};
+use ratatui::text::Line;
+use serde::{Deserialize, Serialize};
+
+use crate::config::{Config, GameSettings};
+use crate::grid::Grid;
+use crate::master;
+use crate::rules::RuleSet;
+use crate::speed::FRAMES_PER_SECOND;
+use crate::ui;
+use crate::utils::{summary, wait_for_key, wait_for_room};
+use crate::versus::Action;
+
+pub const REPLAY_DIR: &str = "replays";
+
+#[derive(Serialize, Deserialize)]
+pub struct Replay {
+    pub settings: GameSettings,
+    pub rules: RuleSet,
+    pub actions: Vec<(u64, Action)>,
+    /// how many frames the game lasted, so a game that was quit stops where it did
+    pub frames: u64,
+    pub score: u32,
+    /// seconds since the Unix epoch
+    pub played_at: u64,
+}
+
+impl Replay {
+    pub fn save(&self) -> Result<PathBuf, String> {
+        std::fs::create_dir_all(REPLAY_DIR).map_err(|e| format!("{}: {}", REPLAY_DIR, e))?;
+        let path = Path::new(REPLAY_DIR).join(format!("{}.json", self.played_at));
+        let serialized = serde_json::to_string(self).map_err(|e| e.to_string())?;
+        std::fs::write(&path, serialized).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Ok(path)
+    }
+
+    pub fn load(path: &Path) -> Result<Replay, String> {
+        let file = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        serde_json::from_str(&file).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    /// every replay that can be read, newest first
+    pub fn load_all() -> Vec<Replay> {
+        let Ok(entries) = std::fs::read_dir(REPLAY_DIR) else {
+            return Vec::new();
+        };
+        let mut replays = entries
+            .filter_map(|entry| Replay::load(&entry.ok()?.path()).ok())
+            .collect::<Vec<Replay>>();
+        replays.sort_by_key(|replay| std::cmp::Reverse(replay.played_at));
+        replays
+    }
+
+    pub fn describe(&self) -> String {
+        let mode = match self.settings.rules {
+            Some(_) => self.rules.name.as_str(),
+            None => self.settings.mode.name(),
+        };
+        format!(
+            "{}{}: {} points in {}",
+            self.settings.visibility.prefix(),
+            mode,
+            self.score,
+            master::format_time(self.frames)
+        )
+    }
+}
+
+/// plays the game back at normal speed, the pause key pauses it and the quit key stops it
+pub fn play(terminal: &mut ratatui::DefaultTerminal, replay: &Replay, cfg: &Config) -> Result<(), String> {
+    let mut grid = crate::new_game(&replay.settings, replay.rules.clone())?;
+    let mut actions = replay.actions.iter().peekable();
+    let mut ended = false;
+    let mut paused = false;
+    let frame_time = Duration::from_secs(1) / FRAMES_PER_SECOND;
+    let mut last_frame = Instant::now();
+    let notes = vec![Line::from("Replay"), Line::from(replay.describe())];
+
+    loop {
+        if wait_for_room(terminal, cfg, ui::min_size(&grid, &cfg.theme), &mut last_frame)? {
+            return Ok(());
+        }
+        terminal
+            .draw(|frame| {
+                ui::draw(frame, grid.clone(), cfg);
+                ui::draw_notes(frame, &grid, cfg, notes.clone());
+            })
+            .map_err(|e| e.to_string())?;
+
+        while last_frame.elapsed() >= frame_time {
+            last_frame += frame_time;
+            if paused {
+                continue;
+            }
+
+            // inputs on a frame came in after it was ticked, the same as while it was played
+            while let Some((_, action)) = actions.next_if(|(frame, _)| *frame <= grid.frames) {
+                apply(&mut grid, *action, &mut ended);
+            }
+            if ended || (actions.peek().is_none() && grid.frames >= replay.frames) {
+                return finish(terminal, &grid, cfg);
+            }
+            grid.tick(|_| ended = true);
+        }
+
+        while event::poll(Duration::from_millis(1)).unwrap() {
+            match event::read() {
+                Ok(Event::Key(key)) if key.kind == KeyEventKind::Press => {
+                    if key.code == cfg.quit.code {
+                        return Ok(());
+                    }
+                    if key.code == cfg.pause.code {
+                        paused = !paused;
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => return Err(e.to_string()),
+            }
+        }
+    }
+}
+
+/// an input as `handle_events` handles it, which clears full rows after every key
+fn apply(grid: &mut Grid, action: Action, ended: &mut bool) {
+    action.apply(grid, |_| *ended = true);
+    grid.remove_full_rows();
+}
+
+fn finish(terminal: &mut ratatui::DefaultTerminal, grid: &Grid, cfg: &Config) -> Result<(), String> {
+    let mut message = vec![Line::from("End of the replay"), Line::from("")];
+    message.extend(summary(grid).into_iter().map(Line::from));
+    message.push(Line::from(""));
+    message.push(Line::from("any key: back"));
+    terminal
+        .draw(|frame| {
+            ui::draw(frame, grid.clone(), cfg);
+            ui::draw_message(frame, message);
+        })
+        .map_err(|e| e.to_string())?;
+    wait_for_key()?;
+    Ok(())
+}
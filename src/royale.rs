@@ -0,0 +1,367 @@
+// src/royale.rs
+//
+// Battle royale puts one player against a crowd of bots, every board dealt the same pieces. Garbage
+// goes to whoever the sender is targeting, picked by their targeting strategy and looked at again
+// every couple of seconds. A board that tops out is knocked out, and the knockout goes to whoever
+// sent it garbage last: they earn a badge plus all the badges of the board they knocked out, and
+// badges make the garbage they send bigger. The last board standing wins.
+
+use std::cell::Cell;
+use std::time::{Duration, Instant};
+
+use clap::ValueEnum;
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use rand::rngs::StdRng;
+use rand::seq::{IteratorRandom, SliceRandom};
+use rand::SeedableRng;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Stylize};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, Paragraph};
+use ratatui::Frame;
+
+use crate::bot::{Bot, BotSettings};
+use crate::config::Config;
+use crate::grid::Grid;
+use crate::menu::Paused;
+use crate::mode::Mode;
+use crate::rules::RuleSet;
+use crate::spectate::Publisher;
+use crate::speed::FRAMES_PER_SECOND;
+use crate::theme::Theme;
+use crate::ui;
+use crate::utils::{pause_with, restarting, wait_for_key, wait_for_room, Round};
+use crate::versus::Action;
+
+/// frames between each board picking its target again
+const RETARGET_FRAMES: u64 = 2 * FRAMES_PER_SECOND as u64;
+/// badges needed for each step up in attack bonus
+const BADGE_LEVELS: [u32; 4] = [2, 6, 14, 30];
+/// extra attack for each badge level reached, in percent
+const BADGE_BONUS: u32 = 25;
+/// cycles the player's targeting strategy
+const TARGETING_KEY: KeyCode = KeyCode::Tab;
+/// width of the player's board with its side panels
+const MAIN_WIDTH: u16 = 64;
+const MINI_WIDTH: u16 = 12;
+const MINI_HEIGHT: u16 = 13;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Targeting {
+    /// anyone still in
+    Random,
+    /// whoever is targeting you
+    Attackers,
+    /// whoever has the tallest stack and is closest to being knocked out
+    Kos,
+    /// whoever has the most badges
+    Badges,
+}
+
+impl Targeting {
+    const ALL: [Targeting; 4] = [Targeting::Random, Targeting::Attackers, Targeting::Kos, Targeting::Badges];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Targeting::Random => "Random",
+            Targeting::Attackers => "Attackers",
+            Targeting::Kos => "KOs",
+            Targeting::Badges => "Badges",
+        }
+    }
+
+    fn next(&self) -> Targeting {
+        let index = Targeting::ALL.iter().position(|targeting| targeting == self).unwrap_or(0);
+        Targeting::ALL[(index + 1) % Targeting::ALL.len()]
+    }
+}
+
+struct Player {
+    grid: Grid,
+    /// `None` for the human player
+    bot: Option<Bot>,
+    alive: bool,
+    targeting: Targeting,
+    target: Option<usize>,
+    /// who sent this board garbage most recently, they get the knockout if it tops out
+    last_attacker: Option<usize>,
+    kos: u32,
+    badges: u32,
+}
+
+impl Player {
+    /// extra percentage added to everything this board sends
+    fn attack_bonus(&self) -> u32 {
+        BADGE_LEVELS.iter().filter(|level| self.badges >= **level).count() as u32 * BADGE_BONUS
+    }
+}
+
+/// who the player is up against, and who they go after to begin with
+#[derive(Clone, Copy)]
+pub struct Field {
+    pub bots: usize,
+    pub bot: BotSettings,
+    pub targeting: Targeting,
+}
+
+/// plays rounds until the player stops asking for another one
+pub fn play(
+    terminal: &mut ratatui::DefaultTerminal,
+    rules: RuleSet,
+    mut cfg: Config,
+    config_file: &str,
+    field: Field,
+    mut publisher: Option<Publisher>,
+) -> Result<(), String> {
+    loop {
+        let Some((players, place)) = restarting(|| play_one(terminal, &rules, &mut cfg, config_file, field, &mut publisher))? else {
+            return Ok(());
+        };
+
+        let message = vec![
+            Line::from(if place == 1 { "You win!".to_string() } else { "Knocked out".to_string() }),
+            Line::from(format!("Place: {} of {}", place, players.len())),
+            Line::from(format!("KOs: {}  Badges: {}", players[0].kos, players[0].badges)),
+            Line::from(""),
+            Line::from("r: play again"),
+            Line::from("any key: quit"),
+        ];
+        terminal
+            .draw(|frame| {
+                draw(frame, &players, &cfg);
+                ui::draw_message(frame, message.clone());
+            })
+            .map_err(|e| e.to_string())?;
+
+        if wait_for_key()? != KeyCode::Char('r') {
+            return Ok(());
+        }
+    }
+}
+
+/// the final boards and the player's place
+type Standing = (Vec<Player>, usize);
+
+/// returns the final boards and the player's place, or `None` if the round was quit
+fn play_one(
+    terminal: &mut ratatui::DefaultTerminal,
+    rules: &RuleSet,
+    cfg: &mut Config,
+    config_file: &str,
+    field: Field,
+    publisher: &mut Option<Publisher>,
+) -> Result<Round<Option<Standing>>, String> {
+    let mut seeded = rules.clone();
+    let seed = seeded.seed.unwrap_or_else(rand::random);
+    seeded.seed = Some(seed);
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let mut players = (0..=field.bots)
+        .map(|index| {
+            let mut grid = Grid::with_rules(seeded.clone(), 1);
+            grid.mode = Mode::Royale;
+            grid.finesse.judged = index == 0 && cfg.shows_faults();
+            grid.next(None);
+            Player {
+                grid,
+                bot: (index > 0).then(|| Bot::new(field.bot, seed.wrapping_add(index as u64))),
+                alive: true,
+                targeting: if index == 0 { field.targeting } else { *Targeting::ALL.choose(&mut rng).unwrap() },
+                target: None,
+                last_attacker: None,
+                kos: 0,
+                badges: 0,
+            }
+        })
+        .collect::<Vec<Player>>();
+    let topped_out = vec![Cell::new(false); players.len()];
+    let frame_time = Duration::from_secs(1) / FRAMES_PER_SECOND;
+    let mut last_frame = Instant::now();
+
+    loop {
+        if wait_for_room(terminal, cfg, ui::min_size(&players[0].grid, &cfg.theme), &mut last_frame)? {
+            return Ok(Round::Over(None));
+        }
+        terminal
+            .draw(|frame| draw(frame, &players, cfg))
+            .map_err(|e| e.to_string())?;
+        if let Some(publisher) = publisher {
+            publisher.publish(&[&players[0].grid]);
+        }
+
+        while last_frame.elapsed() >= frame_time {
+            last_frame += frame_time;
+            for (player, topped_out) in players.iter_mut().zip(&topped_out).filter(|(player, _)| player.alive) {
+                if let Some(bot) = &mut player.bot {
+                    bot.act(&mut player.grid, |_| topped_out.set(true));
+                }
+                player.grid.tick(|_| topped_out.set(true));
+            }
+            retarget(&mut players, &mut rng);
+            route_garbage(&mut players);
+            knock_out(&mut players, &topped_out);
+        }
+
+        while event::poll(Duration::from_millis(1)).unwrap() {
+            let code = match event::read() {
+                Ok(Event::Key(key)) if key.kind == KeyEventKind::Press => key.code,
+                Ok(_) => continue,
+                Err(e) => return Err(e.to_string()),
+            };
+
+            if code == cfg.quit.code {
+                return Ok(Round::Over(None));
+            }
+            if code == cfg.pause.code {
+                match pause_with(terminal, cfg, config_file, &mut last_frame, |frame, cfg| draw(frame, &players, cfg))? {
+                    Paused::Resume => {}
+                    Paused::Restart => return Ok(Round::Restarted),
+                    Paused::Quit => return Ok(Round::Over(None)),
+                }
+                // the stats shown may have been changed in the settings
+                players[0].grid.finesse.judged = cfg.shows_faults();
+                continue;
+            }
+            if code == TARGETING_KEY {
+                players[0].targeting = players[0].targeting.next();
+                players[0].target = None;
+            } else if let Some(action) = Action::from_key(cfg, code) {
+                action.apply(&mut players[0].grid, |_| topped_out[0].set(true));
+            }
+        }
+        route_garbage(&mut players);
+        knock_out(&mut players, &topped_out);
+
+        let alive = players.iter().filter(|player| player.alive).count();
+        if !players[0].alive {
+            return Ok(Round::Over(Some((players, alive + 1))));
+        }
+        if alive == 1 {
+            return Ok(Round::Over(Some((players, 1))));
+        }
+    }
+}
+
+/// picks a new target for every board whose target is gone or that's due to look again
+fn retarget(players: &mut [Player], rng: &mut StdRng) {
+    for index in 0..players.len() {
+        let player = &players[index];
+        let frames = player.grid.frames;
+        let due = frames.is_multiple_of(RETARGET_FRAMES) || player.target.is_none_or(|target| !players[target].alive);
+        if player.alive && due {
+            players[index].target = choose_target(players, index, rng);
+        }
+    }
+}
+
+fn choose_target(players: &[Player], index: usize, rng: &mut StdRng) -> Option<usize> {
+    let others = || (0..players.len()).filter(move |other| *other != index && players[*other].alive);
+
+    match players[index].targeting {
+        Targeting::Random => others().choose(rng),
+        Targeting::Attackers => others()
+            .filter(|other| players[*other].target == Some(index))
+            .choose(rng)
+            .or_else(|| others().choose(rng)),
+        Targeting::Kos => others().max_by_key(|other| stack_height(&players[*other].grid)),
+        Targeting::Badges => others().max_by_key(|other| players[*other].badges),
+    }
+}
+
+fn stack_height(grid: &Grid) -> usize {
+    (0..grid.height)
+        .find(|y| grid.rows[*y].cells.iter().any(|cell| cell.is_full()))
+        .map_or(0, |top| grid.height - top)
+}
+
+/// hands each board's outgoing garbage to its target, with its badge bonus on top
+fn route_garbage(players: &mut [Player]) {
+    for index in 0..players.len() {
+        let lines = std::mem::take(&mut players[index].grid.garbage.outgoing);
+        let Some(target) = players[index].target.filter(|_| lines > 0) else {
+            continue;
+        };
+        if !players[target].alive {
+            continue;
+        }
+
+        let lines = lines + lines * players[index].attack_bonus() / 100;
+        players[target].grid.garbage.incoming.push_back(lines);
+        players[target].last_attacker = Some(index);
+    }
+}
+
+/// takes the boards that topped out out of the game and hands out their badges
+fn knock_out(players: &mut [Player], topped_out: &[Cell<bool>]) {
+    for index in 0..players.len() {
+        if !players[index].alive || !topped_out[index].get() {
+            continue;
+        }
+        players[index].alive = false;
+        players[index].grid.garbage.incoming.clear();
+
+        if let Some(attacker) = players[index].last_attacker.filter(|attacker| players[*attacker].alive) {
+            players[attacker].kos += 1;
+            players[attacker].badges += 1 + players[index].badges;
+        }
+    }
+}
+
+fn draw(frame: &mut Frame, players: &[Player], cfg: &Config) {
+    let theme = &cfg.theme;
+    let area = frame.area();
+    let main_width = MAIN_WIDTH.min(area.width);
+    let side_width = (area.width - main_width) / 2;
+    let main_area = Rect::new(area.x + side_width, area.y, main_width, area.height);
+
+    let me = &players[0];
+    ui::draw_in(frame, main_area, me.grid.clone(), cfg);
+
+    let attackers = players.iter().filter(|player| player.alive && player.target == Some(0)).count();
+    let alive = players.iter().filter(|player| player.alive).count();
+    let notes = vec![
+        Line::from(format!("Alive: {}/{}", alive, players.len())),
+        Line::from(format!("Targeting: {}", me.targeting.name())),
+        Line::from("(Tab to change)"),
+        Line::from(format!("KOs: {}  Badges: {}", me.kos, me.badges)),
+        Line::from(format!("Attack bonus: {}%", me.attack_bonus())),
+        Line::from(format!("Attackers: {}", attackers)).fg(if attackers > 1 { Color::Red } else { Color::White }),
+    ];
+    ui::draw_notes_in(frame, main_area, &me.grid, cfg, notes);
+
+    // the other boards fill up the space on both sides, as many as fit
+    let columns = side_width / MINI_WIDTH;
+    let rows = area.height / MINI_HEIGHT;
+    let slots = (0..2u16).flat_map(|side| {
+        let x = if side == 0 { area.x } else { main_area.x + main_area.width };
+        (0..rows).flat_map(move |row| (0..columns).map(move |column| (x + column * MINI_WIDTH, area.y + row * MINI_HEIGHT)))
+    });
+    for ((x, y), (index, player)) in slots.zip(players.iter().enumerate().skip(1)) {
+        draw_mini(frame, Rect::new(x, y, MINI_WIDTH, MINI_HEIGHT), index, player, me.target == Some(index), theme);
+    }
+}
+
+/// a board at a quarter of the size, two rows of cells to a line
+fn draw_mini(frame: &mut Frame, area: Rect, index: usize, player: &Player, targeted: bool, theme: &Theme) {
+    let lines = match player.alive {
+        false => vec![Line::from(""), Line::from("KO").fg(Color::DarkGray).centered()],
+        true => ui::half_board(&player.grid, theme),
+    };
+
+    let mut title = format!("{}", index + 1);
+    if player.badges > 0 {
+        title.push_str(&format!(" b{}", player.badges));
+    }
+    if player.alive && player.target == Some(0) {
+        title.push('!');
+    }
+    let border = match (targeted, player.alive) {
+        (_, false) => Color::DarkGray,
+        (true, true) => Color::Red,
+        (false, true) => Color::White,
+    };
+    frame.render_widget(
+        Paragraph::new(lines).block(Block::default().borders(Borders::ALL).border_set(theme.cells().border()).title(title).fg(border)),
+        area,
+    );
+}
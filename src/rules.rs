@@ -0,0 +1,196 @@
+// src/rules.rs
+//
+// A rule set describes a game mode as data, so house modes can be added without recompiling. Every
+// field is optional and falls back to the marathon rules, e.g. a 40 line sprint:
+//
+//     {
+//         "name": "Sprint",
+//         "randomizer": "bag",
+//         "preview": 5,
+//         "lock_delay": 30,
+//         "gravity": [[1, 4]],
+//         "goal": { "lines": 40 }
+//     }
+//
+// `gravity` is a list of `[level, gravity]` pairs in 1/256ths of a cell per frame, each level uses
+// the last pair at or below it. `scoring` is the points for clearing 1, 2, 3, ... lines at once,
+// multiplied by the level. `pieces` swaps the tetrominoes for another piece set, see `shape.rs`.
+// `line_clear_delay` holds the next piece back for that many frames after a clear, which is also how
+// long the clear is animated.
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::constants::{ROW_HEIGHT, ROW_WIDTH};
+use crate::grid::Grid;
+use crate::randomizer::RandomizerKind;
+use crate::shape::{self, Piece, Shape};
+use crate::speed::{Speed, FRAMES_PER_SECOND};
+
+/// the widest and tallest board a rule set can ask for
+pub const MAX_SIZE: usize = 100;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RuleSet {
+    pub name: String,
+    pub width: usize,
+    pub height: usize,
+    pub randomizer: RandomizerKind,
+    /// seed for the randomizer and garbage holes, a random one is picked if it's missing
+    pub seed: Option<u64>,
+    /// how many upcoming pieces are shown
+    pub preview: usize,
+    pub hold: bool,
+    /// frames a landed piece stays movable, 0 locks it on contact
+    pub lock_delay: u32,
+    /// frames the next piece is held back after a line clear, while the cleared rows are animated
+    pub line_clear_delay: u32,
+    pub gravity: Vec<(u32, u32)>,
+    pub scoring: Vec<u32>,
+    /// pieces between level ups, 0 never levels up
+    pub pieces_per_level: u32,
+    pub max_level: u32,
+    pub goal: Option<Goal>,
+    pub garbage: Option<GarbageRule>,
+    pub pieces: Vec<Piece>,
+}
+
+impl Default for RuleSet {
+    /// the marathon rules
+    fn default() -> Self {
+        RuleSet {
+            name: "Marathon".to_string(),
+            width: ROW_WIDTH,
+            height: ROW_HEIGHT,
+            randomizer: RandomizerKind::Random,
+            seed: None,
+            preview: 1,
+            hold: true,
+            lock_delay: 0,
+            line_clear_delay: 0,
+            gravity: (1..=10).map(|level| (level, Speed::marathon(level).gravity)).collect(),
+            scoring: vec![40, 100, 300, 1200],
+            pieces_per_level: 25,
+            max_level: 10,
+            goal: None,
+            garbage: None,
+            pieces: shape::tetrominoes(),
+        }
+    }
+}
+
+impl RuleSet {
+    /// the defaults for versus: a 7-bag, a longer preview and a steady 1G-ish speed throughout
+    pub fn versus() -> Self {
+        RuleSet {
+            name: "Versus".to_string(),
+            randomizer: RandomizerKind::Bag,
+            preview: 5,
+            lock_delay: 30,
+            gravity: vec![(1, 4)],
+            pieces_per_level: 0,
+            max_level: 1,
+            ..Default::default()
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<RuleSet, String> {
+        let file = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let mut rules: RuleSet = serde_json::from_str(&file).map_err(|e| format!("{}: {}", path.display(), e))?;
+        rules.validate(1).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Ok(rules)
+    }
+
+    /// checks a rule set that came from outside, a file or the other side of a network game, for a
+    /// board made of `scale`×`scale` cells, and puts the gravity table in order
+    pub fn validate(&mut self, scale: usize) -> Result<(), String> {
+        if !self.width.is_multiple_of(scale) || !self.height.is_multiple_of(scale) {
+            return Err(format!("a board made of {0}x{0} cells needs a width and height divisible by {0}", scale));
+        }
+        if self.width / scale < 4 || self.height / scale < 4 {
+            return Err("the board has to be at least 4x4".to_string());
+        }
+        if self.width > MAX_SIZE || self.height > MAX_SIZE {
+            return Err(format!("the board can be at most {}x{}", MAX_SIZE, MAX_SIZE));
+        }
+        if self.max_level == 0 {
+            return Err("max_level has to be at least 1".to_string());
+        }
+        shape::validate(&self.pieces)?;
+        self.gravity.sort();
+        Ok(())
+    }
+
+    /// the definition of `shape` in this rule set's pieces
+    pub fn piece(&self, shape: Shape) -> Option<&Piece> {
+        self.pieces.iter().find(|piece| piece.name == shape)
+    }
+
+    pub fn shapes(&self) -> Vec<Shape> {
+        self.pieces.iter().map(|piece| piece.name).collect()
+    }
+
+    pub fn speed(&self, level: u32) -> Speed {
+        let gravity = self
+            .gravity
+            .iter()
+            .rev()
+            .find(|(from, _)| *from <= level)
+            .or(self.gravity.first())
+            .map_or(0, |(_, gravity)| *gravity);
+
+        Speed {
+            gravity,
+            lock_delay: self.lock_delay,
+            line_clear_delay: self.line_clear_delay,
+            ..Default::default()
+        }
+    }
+
+    /// points for clearing `lines` lines at once, before the level multiplier
+    pub fn points(&self, lines: usize) -> u32 {
+        lines.checked_sub(1).and_then(|i| self.scoring.get(i)).copied().unwrap_or(0)
+    }
+}
+
+/// ends the game once it's reached
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Goal {
+    Lines(u32),
+    /// seconds, the game ends when the time runs out
+    Time(u32),
+    Score(u32),
+}
+
+impl Goal {
+    pub fn reached(&self, grid: &Grid) -> bool {
+        match self {
+            Goal::Lines(lines) => grid.lines >= *lines,
+            Goal::Time(seconds) => grid.frames >= *seconds as u64 * FRAMES_PER_SECOND as u64,
+            Goal::Score(score) => grid.score >= *score,
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            Goal::Lines(lines) => format!("{} lines", lines),
+            Goal::Time(seconds) => format!("{} seconds", seconds),
+            Goal::Score(score) => format!("{} points", score),
+        }
+    }
+}
+
+/// rows of garbage that rise from the bottom of the board on their own
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GarbageRule {
+    /// add a row every this many pieces, 0 turns it off
+    pub every_pieces: u32,
+    /// add a row every this many seconds, 0 turns it off
+    pub every_seconds: u32,
+    /// chance from 0 to 1 that the hole moves to another column between rows
+    pub messiness: f32,
+}
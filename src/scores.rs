@@ -0,0 +1,100 @@
+// src/scores.rs
+//
+// The best single player games of each mode are kept in `scores.json`, one table per mode with the
+// visibility modifier, big mode and custom rule set names telling tables apart. A mode with a line
+// goal, like a sprint, ranks the games that reached it by time, every other mode ranks by score.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::grid::Grid;
+use crate::master;
+use crate::mode::Mode;
+use crate::rules::Goal;
+
+const SCORES_FILE: &str = "scores.json";
+/// games kept in each table
+const TABLE_SIZE: usize = 10;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Score {
+    pub score: u32,
+    pub lines: u32,
+    pub level: u32,
+    pub frames: u64,
+}
+
+impl Score {
+    pub fn describe(&self) -> String {
+        format!(
+            "{:>8}  {:>4} lines  level {:>3}  {}",
+            self.score,
+            self.lines,
+            self.level,
+            master::format_time(self.frames)
+        )
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct HighScores {
+    pub tables: BTreeMap<String, Vec<Score>>,
+}
+
+impl HighScores {
+    /// a missing or unreadable file is an empty table
+    pub fn load() -> HighScores {
+        std::fs::read_to_string(SCORES_FILE)
+            .ok()
+            .and_then(|file| serde_json::from_str(&file).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let serialized = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        std::fs::write(SCORES_FILE, serialized).map_err(|e| format!("{}: {}", SCORES_FILE, e))
+    }
+
+    /// adds the game on `grid` to its table, returns its place from 1 if it made it in
+    pub fn record(&mut self, grid: &Grid) -> Option<usize> {
+        let by_time = matches!(grid.rules.goal, Some(Goal::Lines(_)));
+        if by_time && !grid.rules.goal.is_some_and(|goal| goal.reached(grid)) {
+            return None;
+        }
+        if !by_time && grid.score == 0 {
+            return None;
+        }
+
+        let score = Score {
+            score: grid.score,
+            lines: grid.lines,
+            level: grid.level,
+            frames: grid.frames,
+        };
+        let table = self.tables.entry(table_name(grid)).or_default();
+        let place = match by_time {
+            true => table.iter().position(|other| score.frames < other.frames),
+            false => table.iter().position(|other| score.score > other.score),
+        }
+        .unwrap_or(table.len());
+        if place >= TABLE_SIZE {
+            return None;
+        }
+
+        table.insert(place, score);
+        table.truncate(TABLE_SIZE);
+        Some(place + 1)
+    }
+}
+
+/// the table a game goes in, e.g. "Invisible Marathon" or "Sprint (big)"
+pub fn table_name(grid: &Grid) -> String {
+    let mode = match grid.mode {
+        Mode::Custom => grid.rules.name.clone(),
+        mode => mode.name().to_string(),
+    };
+    let big = if grid.scale > 1 { " (big)" } else { "" };
+    format!("{}{}{}", grid.visibility.prefix(), mode, big)
+}
@@ -1,56 +1,201 @@
-use ratatui::{
-    style::{Color, Stylize},
-    text::Line,
-};
-
 // src/shape.rs
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum Shape {
-    I,
-    O,
-    T,
-    S,
-    Z,
-    J,
-    L,
-}
+//
+// Pieces are data: a piece set is a list of definitions loaded from a JSON file with `--pieces`, or
+// given as `pieces` in a rule set, and the tetrominoes are the default set. A definition looks like
+//
+//     {
+//         "name": "T",
+//         "rotations": [
+//             [[0, 0], [-1, 0], [1, 0], [0, 1]],
+//             [[0, 0], [0, 1], [0, -1], [1, 0]],
+//             [[0, 0], [1, 0], [-1, 0], [0, -1]],
+//             [[0, 0], [0, -1], [0, 1], [-1, 0]]
+//         ],
+//         "color": "#ffa500",
+//         "spin": true
+//     }
+//
+// `rotations` are the cells of each rotation state as `[x, y]` with y going down, in the order the
+// rotate key turns through them. They're all relative to the same point, so a piece turns around
+// [0, 0]. A piece spawns in its first state with that point on the top row in the middle of the
+// board, moved by `spawn` if it's given. `kicks` lists, for each state, the offsets tried in turn
+// when rotating out of it on the spot is blocked; rotating the other way tries them negated. A
+// `spin` piece rotated into place with three corners around [0, 0] filled scores as a t-spin.
+// `color` is a colour name like "red" or an "#rrggbb" value. The name is a single letter, which
+// is also how boards in puzzles, drills and spectator streams refer to the piece.
+
+use std::path::Path;
+use std::str::FromStr;
+
+use ratatui::style::Color;
+use serde::{Deserialize, Serialize};
+
+/// a piece, by the letter it's named after
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(try_from = "char", into = "char")]
+pub struct Shape(char);
 
 impl Shape {
-    pub fn random() -> Shape {
-        use rand::Rng;
-        match rand::thread_rng().gen_range(0..7) {
-            0 => Shape::I,
-            1 => Shape::O,
-            2 => Shape::T,
-            3 => Shape::S,
-            4 => Shape::Z,
-            5 => Shape::J,
-            _ => Shape::L,
+    pub fn from_char(c: char) -> Option<Shape> {
+        c.is_ascii_alphabetic().then(|| Shape(c.to_ascii_uppercase()))
+    }
+
+    pub fn to_char(self) -> char {
+        self.0
+    }
+}
+
+impl TryFrom<char> for Shape {
+    type Error = String;
+
+    fn try_from(c: char) -> Result<Self, Self::Error> {
+        Shape::from_char(c).ok_or_else(|| format!("{:?} isn't a piece name, pieces are named by a letter", c))
+    }
+}
+
+impl From<Shape> for char {
+    fn from(shape: Shape) -> char {
+        shape.0
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Piece {
+    pub name: Shape,
+    pub rotations: Vec<Vec<(i32, i32)>>,
+    #[serde(default)]
+    pub spawn: (i32, i32),
+    pub color: String,
+    #[serde(default)]
+    pub kicks: Vec<Vec<(i32, i32)>>,
+    #[serde(default)]
+    pub spin: bool,
+}
+
+impl Piece {
+    fn new(name: char, rotations: &[&[(i32, i32)]], color: &str) -> Piece {
+        Piece {
+            name: Shape(name),
+            rotations: rotations.iter().map(|cells| cells.to_vec()).collect(),
+            spawn: (0, 0),
+            color: color.to_string(),
+            kicks: Vec::new(),
+            spin: false,
         }
     }
 
-    pub fn color(&self) -> Vec<Line<'static>> {
-        let mut lines = Vec::with_capacity(4);
-        let shape_repr = match self {
-            Shape::I => crate::constants::I,
-            Shape::O => crate::constants::O,
-            Shape::T => crate::constants::T,
-            Shape::S => crate::constants::S,
-            Shape::Z => crate::constants::Z,
-            Shape::J => crate::constants::J,
-            Shape::L => crate::constants::L,
-        };
-        for line in shape_repr.split('\n') {
-            lines.push(Line::from(line).fg(match self {
-                Shape::I => Color::Red,
-                Shape::O => Color::Blue,
-                Shape::T => Color::Rgb(255, 165, 0),
-                Shape::S => Color::Green,
-                Shape::Z => Color::Cyan,
-                Shape::J => Color::White,
-                Shape::L => Color::Magenta,
-            }));
+    pub fn color(&self) -> Color {
+        Color::from_str(&self.color).unwrap_or(Color::White)
+    }
+
+    /// the state turning from `state` leads to, and the offsets to try if turning on the spot is blocked
+    pub fn turn(&self, state: usize, clockwise: bool) -> (usize, Vec<(i32, i32)>) {
+        let states = self.rotations.len();
+        if clockwise {
+            ((state + 1) % states, self.kicks.get(state).cloned().unwrap_or_default())
+        } else {
+            let to = (state + states - 1) % states;
+            (to, self.kicks.get(to).map_or(Vec::new(), |kicks| kicks.iter().map(|(x, y)| (-x, -y)).collect()))
+        }
+    }
+
+    /// the first rotation state a line at a time, drawn with `block` and `blank`
+    pub fn preview(&self, block: &str, blank: &str) -> Vec<String> {
+        let cells = &self.rotations[0];
+        let min_x = cells.iter().map(|(x, _)| *x).min().unwrap_or(0);
+        let max_x = cells.iter().map(|(x, _)| *x).max().unwrap_or(0);
+        let min_y = cells.iter().map(|(_, y)| *y).min().unwrap_or(0);
+        let max_y = cells.iter().map(|(_, y)| *y).max().unwrap_or(0);
+
+        (min_y..=max_y)
+            .map(|y| {
+                let line = (min_x..=max_x)
+                    .map(|x| if cells.contains(&(x, y)) { block } else { blank })
+                    .collect::<String>();
+                line.trim_end().to_string()
+            })
+            .collect()
+    }
+}
+
+/// the seven tetrominoes, spawning the way they always have: I on end, the T pointing down
+pub fn tetrominoes() -> Vec<Piece> {
+    let mut t = Piece::new(
+        'T',
+        &[
+            &[(0, 0), (-1, 0), (1, 0), (0, 1)],
+            &[(0, 0), (0, 1), (0, -1), (1, 0)],
+            &[(0, 0), (1, 0), (-1, 0), (0, -1)],
+            &[(0, 0), (0, -1), (0, 1), (-1, 0)],
+        ],
+        "#ffa500",
+    );
+    t.spin = true;
+
+    vec![
+        Piece::new(
+            'I',
+            &[&[(0, 0), (0, 1), (0, 2), (0, 3)], &[(-1, 1), (0, 1), (1, 1), (2, 1)]],
+            "red",
+        ),
+        Piece::new('O', &[&[(0, 0), (1, 0), (0, 1), (1, 1)]], "blue"),
+        t,
+        Piece::new(
+            'S',
+            &[&[(0, 0), (1, 0), (0, 1), (-1, 1)], &[(0, 0), (0, -1), (1, 0), (1, 1)]],
+            "green",
+        ),
+        Piece::new(
+            'Z',
+            &[&[(0, 0), (-1, 0), (0, 1), (1, 1)], &[(0, 0), (0, 1), (1, 0), (1, -1)]],
+            "cyan",
+        ),
+        Piece::new(
+            'J',
+            &[
+                &[(0, 0), (-1, 0), (1, 0), (1, 1)],
+                &[(0, 0), (0, 1), (0, -1), (1, -1)],
+                &[(0, 0), (1, 0), (-1, 0), (-1, -1)],
+                &[(0, 0), (0, -1), (0, 1), (-1, 1)],
+            ],
+            "white",
+        ),
+        Piece::new(
+            'L',
+            &[
+                &[(0, 0), (-1, 0), (1, 0), (-1, 1)],
+                &[(0, 0), (0, 1), (0, -1), (1, 1)],
+                &[(0, 0), (1, 0), (-1, 0), (1, -1)],
+                &[(0, 0), (0, -1), (0, 1), (-1, -1)],
+            ],
+            "magenta",
+        ),
+    ]
+}
+
+pub fn load_pieces(path: &Path) -> Result<Vec<Piece>, String> {
+    let file = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let pieces: Vec<Piece> = serde_json::from_str(&file).map_err(|e| format!("{}: {}", path.display(), e))?;
+    validate(&pieces).map_err(|e| format!("{}: {}", path.display(), e))?;
+    Ok(pieces)
+}
+
+/// checks a piece set can be played with
+pub fn validate(pieces: &[Piece]) -> Result<(), String> {
+    if pieces.is_empty() {
+        return Err("there has to be at least one piece".to_string());
+    }
+    for (i, piece) in pieces.iter().enumerate() {
+        let name = piece.name.to_char();
+        if pieces[..i].iter().any(|other| other.name == piece.name) {
+            return Err(format!("there's more than one piece named {}", name));
+        }
+        if piece.rotations.is_empty() || piece.rotations.iter().any(|cells| cells.is_empty()) {
+            return Err(format!("piece {} needs at least one rotation state, each with at least one cell", name));
+        }
+        if Color::from_str(&piece.color).is_err() {
+            return Err(format!("piece {} has an unknown colour {:?}", name, piece.color));
         }
-        lines
     }
+    Ok(())
 }
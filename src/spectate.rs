@@ -0,0 +1,338 @@
+// src/spectate.rs
+//
+// A game started with `--publish <addr>` streams its boards to anyone watching with `tetris watch
+// <addr>`. Every line sent is a JSON array with one snapshot per board, e.g. for a single game
+//
+//     [{"mode":"Marathon","name":"Marathon","width":10,"height":20,"scale":1,
+//       "rows":["..........", ..., "...tt.....", "..ttI#...."],
+//       "queue":["O"],"hold":null,"score":40,"level":1,"lines":1,"frames":900,"garbage":0,
+//       "pieces":[{"name":"I", ...}, ...]}]
+//
+// In `rows` a `.` is an empty cell, an upper case letter a locked block of that piece, a lower case
+// letter the active piece and `#` garbage. Blocks hidden by `--invisible` or `--fade` are sent as
+// empty cells. `pieces` is the piece set, as in `shape.rs`, so watchers draw it in its own colours.
+// The address is `host:port` for TCP or `unix:<path>` for a Unix socket. A snapshot is checked like
+// a rule set before it's drawn.
+
+use std::collections::VecDeque;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+use crossterm::event::{self, Event, KeyEventKind};
+use ratatui::text::Line;
+use serde::{Deserialize, Serialize};
+
+use crate::block::Block;
+use crate::config::Config;
+use crate::grid::Grid;
+use crate::mode::Mode;
+use crate::rules::{Goal, RuleSet};
+use crate::shape::{self, Piece, Shape};
+use crate::ui;
+use crate::utils::wait_for_key;
+use crate::versus;
+
+/// boards are sent at most this often, watchers don't need every frame
+const PUBLISH_INTERVAL: Duration = Duration::from_millis(33);
+/// cells are at most 2x2, as with `--big`
+const MAX_SCALE: usize = 2;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub mode: Mode,
+    pub name: String,
+    pub width: usize,
+    pub height: usize,
+    pub scale: usize,
+    pub rows: Vec<String>,
+    pub queue: Vec<Shape>,
+    pub hold: Option<Shape>,
+    #[serde(default)]
+    pub goal: Option<Goal>,
+    pub score: u32,
+    pub level: u32,
+    pub lines: u32,
+    pub frames: u64,
+    /// lines of incoming garbage
+    pub garbage: u32,
+    #[serde(default = "shape::tetrominoes")]
+    pub pieces: Vec<Piece>,
+}
+
+impl Snapshot {
+    /// the board as the player sees it
+    pub fn of(grid: &Grid) -> Snapshot {
+        let rows = grid
+            .rows
+            .iter()
+            .enumerate()
+            .map(|(y, row)| {
+                row.cells
+                    .iter()
+                    .enumerate()
+                    .map(|(x, cell)| match cell {
+                        _ if grid.is_hidden((x, y)) => '.',
+                        Block::Full(shape) => shape.to_char(),
+                        Block::Active(shape) => shape.to_char().to_ascii_lowercase(),
+                        Block::Garbage => '#',
+                        Block::Empty => '.',
+                    })
+                    .collect()
+            })
+            .collect();
+
+        Snapshot {
+            mode: grid.mode,
+            name: grid.rules.name.clone(),
+            width: grid.width,
+            height: grid.height,
+            scale: grid.scale,
+            rows,
+            queue: grid.next_shapes.iter().take(grid.rules.preview).copied().collect(),
+            hold: grid.held_shape,
+            goal: grid.rules.goal,
+            score: grid.score,
+            level: grid.level,
+            lines: grid.lines,
+            frames: grid.frames,
+            garbage: grid.garbage.pending(),
+            pieces: grid.rules.pieces.clone(),
+        }
+    }
+
+    /// a grid that draws the same as the one the snapshot was taken of
+    pub fn grid(&self) -> Result<Grid, String> {
+        if self.scale == 0 || self.scale > MAX_SCALE {
+            return Err(format!("the board's cells can't be {0}x{0}", self.scale));
+        }
+        let mut rules = RuleSet {
+            name: self.name.clone(),
+            width: self.width.saturating_mul(self.scale),
+            height: self.height.saturating_mul(self.scale),
+            preview: self.queue.len(),
+            goal: self.goal,
+            seed: Some(0),
+            pieces: self.pieces.clone(),
+            ..Default::default()
+        };
+        rules.validate(self.scale)?;
+        let mut grid = Grid::with_rules(rules, self.scale);
+
+        for (y, row) in self.rows.iter().enumerate().take(grid.height) {
+            for (x, c) in row.chars().enumerate().take(grid.width) {
+                let block = match (c, Shape::from_char(c)) {
+                    ('.', _) => Block::Empty,
+                    (c, Some(shape)) if c.is_ascii_lowercase() => {
+                        grid.active_shape = Some(shape);
+                        Block::Active(shape)
+                    }
+                    (_, Some(shape)) => Block::Full(shape),
+                    (_, None) => Block::Garbage,
+                };
+                grid.set((x, y), block);
+            }
+        }
+
+        grid.mode = self.mode;
+        grid.next_shapes = VecDeque::from(self.queue.clone());
+        grid.held_shape = self.hold;
+        grid.score = self.score;
+        grid.level = self.level;
+        grid.lines = self.lines;
+        grid.frames = self.frames;
+        if self.garbage > 0 {
+            grid.garbage.incoming.push_back(self.garbage);
+        }
+        Ok(grid)
+    }
+}
+
+enum Listener {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(UnixListener),
+}
+
+/// sends snapshots to everyone watching
+pub struct Publisher {
+    listener: Listener,
+    watchers: Vec<Box<dyn Write>>,
+    last_sent: Option<Instant>,
+}
+
+impl Publisher {
+    pub fn bind(addr: &str) -> Result<Publisher, String> {
+        let listener = match addr.strip_prefix("unix:") {
+            #[cfg(unix)]
+            Some(path) => {
+                // a socket left behind by an earlier game would stop the bind
+                let _ = std::fs::remove_file(path);
+                let listener = UnixListener::bind(path).map_err(|e| format!("{}: {}", addr, e))?;
+                listener.set_nonblocking(true).map_err(|e| e.to_string())?;
+                Listener::Unix(listener)
+            }
+            #[cfg(not(unix))]
+            Some(_) => return Err(format!("{}: Unix sockets aren't supported here", addr)),
+            None => {
+                let listener = TcpListener::bind(addr).map_err(|e| format!("{}: {}", addr, e))?;
+                listener.set_nonblocking(true).map_err(|e| e.to_string())?;
+                Listener::Tcp(listener)
+            }
+        };
+
+        Ok(Publisher {
+            listener,
+            watchers: Vec::new(),
+            last_sent: None,
+        })
+    }
+
+    /// lets in new watchers and sends them all the boards, unless a snapshot went out very recently
+    pub fn publish(&mut self, grids: &[&Grid]) {
+        if self.last_sent.is_some_and(|sent| sent.elapsed() < PUBLISH_INTERVAL) {
+            return;
+        }
+        self.last_sent = Some(Instant::now());
+        self.accept();
+        if self.watchers.is_empty() {
+            return;
+        }
+
+        let snapshots = grids.iter().map(|grid| Snapshot::of(grid)).collect::<Vec<Snapshot>>();
+        let mut line = serde_json::to_string(&snapshots).expect("snapshots always serialize");
+        line.push('\n');
+        // anyone who can't keep up or has gone away stops watching
+        self.watchers.retain_mut(|watcher| watcher.write_all(line.as_bytes()).is_ok());
+    }
+
+    fn accept(&mut self) {
+        loop {
+            let watcher: io::Result<Box<dyn Write>> = match &self.listener {
+                Listener::Tcp(listener) => listener.accept().and_then(|(stream, _)| {
+                    stream.set_nonblocking(false)?;
+                    stream.set_write_timeout(Some(PUBLISH_INTERVAL))?;
+                    Ok(Box::new(stream) as Box<dyn Write>)
+                }),
+                #[cfg(unix)]
+                Listener::Unix(listener) => listener.accept().and_then(|(stream, _)| {
+                    stream.set_nonblocking(false)?;
+                    stream.set_write_timeout(Some(PUBLISH_INTERVAL))?;
+                    Ok(Box::new(stream) as Box<dyn Write>)
+                }),
+            };
+            match watcher {
+                Ok(watcher) => self.watchers.push(watcher),
+                Err(_) => return,
+            }
+        }
+    }
+}
+
+/// draws the boards published at `addr` until the game ends or the quit key is pressed
+pub fn watch(terminal: &mut ratatui::DefaultTerminal, addr: &str, cfg: Config) -> Result<(), String> {
+    let reader: Box<dyn BufRead + Send> = match addr.strip_prefix("unix:") {
+        #[cfg(unix)]
+        Some(path) => Box::new(BufReader::new(UnixStream::connect(path).map_err(|e| format!("{}: {}", addr, e))?)),
+        #[cfg(not(unix))]
+        Some(_) => return Err(format!("{}: Unix sockets aren't supported here", addr)),
+        None => Box::new(BufReader::new(TcpStream::connect(addr).map_err(|e| format!("{}: {}", addr, e))?)),
+    };
+
+    let (tx, snapshots) = mpsc::channel();
+    std::thread::spawn(move || {
+        for line in reader.lines() {
+            let Ok(line) = line else { break };
+            if let Ok(boards) = serde_json::from_str::<Vec<Snapshot>>(&line) {
+                if tx.send(boards).is_err() {
+                    break;
+                }
+            }
+        }
+    });
+
+    let mut grids = Vec::new();
+    loop {
+        loop {
+            match snapshots.try_recv() {
+                Ok(boards) => {
+                    grids = boards
+                        .iter()
+                        .map(Snapshot::grid)
+                        .collect::<Result<Vec<Grid>, String>>()
+                        .map_err(|e| format!("{}: {}", addr, e))?
+                }
+                Err(mpsc::TryRecvError::Empty) => break,
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    terminal
+                        .draw(|frame| {
+                            draw(frame, &grids, &cfg.without_help());
+                            ui::draw_message(frame, vec![Line::from("The game has ended"), Line::from(""), Line::from("any key: quit")]);
+                        })
+                        .map_err(|e| e.to_string())?;
+                    wait_for_key()?;
+                    return Ok(());
+                }
+            }
+        }
+
+        terminal
+            .draw(|frame| {
+                draw(frame, &grids, &cfg.without_help());
+                if grids.is_empty() {
+                    ui::draw_message(frame, vec![Line::from(format!("Waiting for {}", addr))]);
+                }
+            })
+            .map_err(|e| e.to_string())?;
+
+        if event::poll(Duration::from_millis(10)).unwrap() {
+            if let Ok(Event::Key(key)) = event::read() {
+                if key.kind == KeyEventKind::Press && key.code == cfg.quit.code {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+fn draw(frame: &mut ratatui::Frame, grids: &[Grid], cfg: &Config) {
+    match grids {
+        [] => {}
+        [grid] => ui::draw(frame, grid.clone(), cfg),
+        [first, second, ..] => versus::draw(
+            frame,
+            &[first.clone(), second.clone()],
+            ["Player 1".to_string(), "Player 2".to_string()],
+            &[cfg.clone(), cfg.clone()],
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapshots_keep_the_piece_set() {
+        let mut rules = RuleSet::default();
+        rules.pieces.retain(|piece| piece.name.to_char() != 'I');
+        rules.pieces[0].color = "#123456".to_string();
+        let grid = Snapshot::of(&Grid::with_rules(rules.clone(), 1)).grid().unwrap();
+        assert_eq!(grid.rules.shapes(), rules.shapes());
+        assert_eq!(grid.rules.pieces[0].color, "#123456");
+    }
+
+    #[test]
+    fn snapshots_are_checked_before_they_are_drawn() {
+        let snapshot = Snapshot::of(&Grid::new());
+        assert!(snapshot.grid().is_ok());
+        assert!(Snapshot { width: 0, ..snapshot.clone() }.grid().is_err());
+        assert!(Snapshot { height: usize::MAX, ..snapshot.clone() }.grid().is_err());
+        assert!(Snapshot { scale: 0, ..snapshot.clone() }.grid().is_err());
+        assert!(Snapshot { scale: 1000, ..snapshot.clone() }.grid().is_err());
+        assert!(Snapshot { pieces: Vec::new(), ..snapshot }.grid().is_err());
+    }
+}
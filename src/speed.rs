@@ -0,0 +1,32 @@
+// src/speed.rs
+
+/// gravity is measured in 1/256ths of a cell per frame, so 256 is 1G and 5120 is 20G
+pub const GRAVITY_UNIT: u32 = 256;
+pub const FRAMES_PER_SECOND: u32 = 60;
+/// lock delay for modes without gravity, where a landed piece waits for a hard drop
+pub const NO_LOCK: u32 = u32::MAX;
+
+/// timings that depend on the mode and level, all measured in frames
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Speed {
+    pub gravity: u32,
+    /// how long a landed piece stays movable before it locks, 0 locks it on contact
+    pub lock_delay: u32,
+    /// delay between a piece locking and the next one spawning
+    pub are: u32,
+    /// added on top of `are` when the lock cleared lines
+    pub line_clear_delay: u32,
+}
+
+impl Speed {
+    /// the original marathon curve: one cell every `500 * (11 - level) / 10` milliseconds
+    pub fn marathon(level: u32) -> Self {
+        let interval_ms = (500.0 * ((11.0 - (level as f32)) / 10.0)).ceil();
+        let gravity = (GRAVITY_UNIT as f32 * 1000.0 / (FRAMES_PER_SECOND as f32 * interval_ms)).round() as u32;
+
+        Speed {
+            gravity,
+            ..Default::default()
+        }
+    }
+}
@@ -0,0 +1,73 @@
+// src/stats.rs
+//
+// Live statistics for the stats panel under the game info. The board keeps the counts that aren't
+// tracked anywhere else, the key presses and how many of each piece were placed, and the rates are
+// worked out from those and the frame count. Which stats are shown, and in what order, is set with
+// `stats` in the config.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::grid::Grid;
+use crate::shape::Shape;
+use crate::speed::FRAMES_PER_SECOND;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Stat {
+    /// time played
+    Time,
+    Lines,
+    /// pieces placed
+    Pieces,
+    /// pieces per second
+    Pps,
+    /// key presses per piece
+    Kpp,
+    /// lines of attack per minute
+    Apm,
+    /// pieces placed with more key presses than needed
+    Faults,
+    Combo,
+    /// whether the next tetris or t-spin is back to back
+    B2b,
+    /// how many of each piece were placed, as bars
+    Histogram,
+}
+
+#[derive(Clone, Default)]
+pub struct Stats {
+    pub keys: u32,
+    pub placed: BTreeMap<Shape, u32>,
+}
+
+/// what the stats panel shows when the config doesn't say
+pub fn default_stats() -> Vec<Stat> {
+    vec![Stat::Time, Stat::Lines, Stat::Pieces, Stat::Pps, Stat::Apm]
+}
+
+fn minutes(grid: &Grid) -> f32 {
+    grid.frames as f32 / FRAMES_PER_SECOND as f32 / 60.0
+}
+
+pub fn pps(grid: &Grid) -> f32 {
+    match grid.frames {
+        0 => 0.0,
+        _ => grid.pieces_locked as f32 / (minutes(grid) * 60.0),
+    }
+}
+
+pub fn kpp(grid: &Grid) -> f32 {
+    match grid.pieces_locked {
+        0 => 0.0,
+        pieces => grid.stats.keys as f32 / pieces as f32,
+    }
+}
+
+pub fn apm(grid: &Grid) -> f32 {
+    match grid.frames {
+        0 => 0.0,
+        _ => grid.garbage.attack as f32 / minutes(grid),
+    }
+}
@@ -0,0 +1,332 @@
+// src/theme.rs
+//
+// A theme sets the colours the game is drawn in: the pieces, the ghost of the falling piece, garbage,
+// borders and text, and the glyphs the cells are drawn with. It's kept in the config file, and
+// `--theme` or the settings menu swap it for one of the presets or a theme file, which looks like
+//
+//     {
+//         "name": "Dusk",
+//         "pieces": { "I": "#56b4e9", "O": "yellow" },
+//         "ghost": "darkgray",
+//         "garbage": "gray",
+//         "border": "white",
+//         "text": "white",
+//         "glyphs": "blocks",
+//         "empty": "dotted"
+//     }
+//
+// Colours are written the same way as in piece sets. Pieces left out of `pieces` are drawn in
+// `all_pieces` if it's given, otherwise in the colour from their piece set. `glyphs` is one of
+// "blocks", "ascii", "letters" or "half", and `empty` one of "dotted", "blank" or "grid". Half blocks
+// fit two rows of the board into each line, which takes a board down to a quarter of the size for
+// small panes and versus or battle royale. Blocks, half blocks and the grid need a UTF-8 terminal, so
+// without a UTF-8 locale they're drawn in ASCII instead.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::str::FromStr;
+
+use ratatui::style::Color;
+use ratatui::symbols::border;
+use serde::{Deserialize, Serialize};
+
+use crate::rules::RuleSet;
+use crate::shape::Shape;
+
+/// the presets, in the order the settings menu goes through them
+pub const PRESETS: [&str; 5] = ["guideline", "classic", "monochrome", "high-contrast", "colorblind"];
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct Theme {
+    pub name: String,
+    pub pieces: BTreeMap<Shape, String>,
+    pub all_pieces: Option<String>,
+    pub ghost: String,
+    pub garbage: String,
+    pub border: String,
+    pub text: String,
+    pub glyphs: Glyphs,
+    pub empty: EmptyCells,
+}
+
+/// what blocks are drawn with
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Glyphs {
+    /// solid blocks
+    #[default]
+    Blocks,
+    /// `[]`, for fonts and consoles that get block characters wrong
+    Ascii,
+    /// the piece's letter in brackets, three columns to a cell
+    Letters,
+    /// `▀` and `▄` in the colours of the cells above and below each other, one column to a cell
+    Half,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum EmptyCells {
+    #[default]
+    Dotted,
+    Blank,
+    /// lines between the cells
+    Grid,
+}
+
+impl Glyphs {
+    pub const ALL: [Glyphs; 4] = [Glyphs::Blocks, Glyphs::Ascii, Glyphs::Letters, Glyphs::Half];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Glyphs::Blocks => "Blocks",
+            Glyphs::Ascii => "ASCII",
+            Glyphs::Letters => "Letters",
+            Glyphs::Half => "Half blocks",
+        }
+    }
+}
+
+impl EmptyCells {
+    pub const ALL: [EmptyCells; 3] = [EmptyCells::Dotted, EmptyCells::Blank, EmptyCells::Grid];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            EmptyCells::Dotted => "Dotted",
+            EmptyCells::Blank => "Blank",
+            EmptyCells::Grid => "Grid",
+        }
+    }
+}
+
+/// the strings each kind of cell is drawn with, all of them `width` columns wide
+pub struct Cells {
+    pub width: u16,
+    /// whether there's more than ASCII to draw with
+    pub unicode: bool,
+    glyphs: Glyphs,
+    empty: EmptyCells,
+}
+
+impl Cells {
+    /// a locked or falling block of `shape`
+    pub fn block(&self, shape: Shape) -> String {
+        match self.glyphs {
+            Glyphs::Blocks => "██".to_string(),
+            Glyphs::Ascii => "[]".to_string(),
+            Glyphs::Letters => format!("[{}]", shape.to_char()),
+            Glyphs::Half => "█".to_string(),
+        }
+    }
+
+    pub fn garbage(&self) -> String {
+        match self.glyphs {
+            Glyphs::Blocks => "██",
+            Glyphs::Ascii => "##",
+            Glyphs::Letters => "[#]",
+            Glyphs::Half => "█",
+        }
+        .to_string()
+    }
+
+    /// where the falling piece would land
+    pub fn ghost(&self) -> String {
+        match self.glyphs {
+            Glyphs::Blocks => "░░",
+            Glyphs::Ascii => "::",
+            Glyphs::Letters => "[ ]",
+            Glyphs::Half => "░",
+        }
+        .to_string()
+    }
+
+    pub fn empty(&self) -> String {
+        let width = self.width as usize;
+        match (self.empty, self.unicode) {
+            (EmptyCells::Dotted, _) => format!("{:^width$}", "."),
+            (EmptyCells::Blank, _) => " ".repeat(width),
+            (EmptyCells::Grid, true) => format!("┼{}", "─".repeat(width - 1)),
+            (EmptyCells::Grid, false) => format!("+{}", "-".repeat(width - 1)),
+        }
+    }
+
+    /// whether each line holds two rows of cells, drawn by colour alone
+    pub fn half(&self) -> bool {
+        self.glyphs == Glyphs::Half
+    }
+
+    /// the lines it takes to draw `rows` rows of cells
+    pub fn lines(&self, rows: u16) -> u16 {
+        if self.half() { rows.div_ceil(2) } else { rows }
+    }
+
+    /// a gap in a piece preview
+    pub fn blank(&self) -> String {
+        " ".repeat(self.width as usize)
+    }
+
+    /// one line of the incoming garbage meter
+    pub fn meter(&self) -> &'static str {
+        if self.unicode { "█" } else { "#" }
+    }
+
+    pub fn border(&self) -> border::Set {
+        match self.unicode {
+            true => border::PLAIN,
+            false => border::Set {
+                top_left: "+",
+                top_right: "+",
+                bottom_left: "+",
+                bottom_right: "+",
+                vertical_left: "|",
+                vertical_right: "|",
+                horizontal_top: "-",
+                horizontal_bottom: "-",
+            },
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::preset("guideline").unwrap()
+    }
+}
+
+impl Theme {
+    /// one of `PRESETS`
+    pub fn preset(name: &str) -> Option<Theme> {
+        let theme = |pieces: &[&str], ghost: &str, garbage: &str, border: &str, text: &str| Theme {
+            name: name.to_string(),
+            pieces: "IOTSZJL".chars().filter_map(Shape::from_char).zip(pieces.iter().map(|color| color.to_string())).collect(),
+            all_pieces: None,
+            ghost: ghost.to_string(),
+            garbage: garbage.to_string(),
+            border: border.to_string(),
+            text: text.to_string(),
+            glyphs: Glyphs::Blocks,
+            empty: EmptyCells::Dotted,
+        };
+
+        Some(match name {
+            "guideline" => theme(&["cyan", "yellow", "magenta", "green", "red", "blue", "#ffa500"], "darkgray", "gray", "white", "white"),
+            // the colours the pieces were given in their piece set
+            "classic" => theme(&[], "darkgray", "darkgray", "white", "white"),
+            // without colours, the letters tell the pieces apart
+            "monochrome" => Theme {
+                all_pieces: Some("white".to_string()),
+                glyphs: Glyphs::Letters,
+                ..theme(&[], "darkgray", "gray", "white", "white")
+            },
+            "high-contrast" => theme(
+                &["lightcyan", "lightyellow", "lightmagenta", "lightgreen", "lightred", "lightblue", "white"],
+                "gray",
+                "white",
+                "white",
+                "white",
+            ),
+            // the Okabe-Ito palette, told apart with deuteranopia and protanopia
+            "colorblind" => theme(
+                &["#56b4e9", "#f0e442", "#cc79a7", "#009e73", "#d55e00", "#0072b2", "#e69f00"],
+                "darkgray",
+                "gray",
+                "white",
+                "white",
+            ),
+            _ => return None,
+        })
+    }
+
+    pub fn load(path: &Path) -> Result<Theme, String> {
+        let file = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let theme: Theme = serde_json::from_str(&file).map_err(|e| format!("{}: {}", path.display(), e))?;
+        theme.validate().map_err(|e| format!("{}: {}", path.display(), e))?;
+        Ok(theme)
+    }
+
+    /// a preset by name, or else a theme file
+    pub fn find(name: &str) -> Result<Theme, String> {
+        match Theme::preset(name) {
+            Some(theme) => Ok(theme),
+            None if Path::new(name).exists() => Theme::load(Path::new(name)),
+            None => Err(format!("{} isn't a theme file or one of the themes: {}", name, PRESETS.join(", "))),
+        }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        let colors = self
+            .pieces
+            .values()
+            .chain(&self.all_pieces)
+            .chain([&self.ghost, &self.garbage, &self.border, &self.text]);
+        for color in colors {
+            if Color::from_str(color).is_err() {
+                return Err(format!("unknown colour {:?}", color));
+            }
+        }
+        Ok(())
+    }
+
+    /// a piece missing from the rule set's pieces, e.g. in a board streamed from a game with other pieces, is drawn grey
+    pub fn piece(&self, rules: &RuleSet, shape: Shape) -> Color {
+        match self.pieces.get(&shape).or(self.all_pieces.as_ref()) {
+            Some(color) => parse(color),
+            None => rules.piece(shape).map_or(Color::Gray, |piece| piece.color()),
+        }
+    }
+
+    pub fn ghost(&self) -> Color {
+        parse(&self.ghost)
+    }
+
+    pub fn garbage(&self) -> Color {
+        parse(&self.garbage)
+    }
+
+    pub fn border(&self) -> Color {
+        parse(&self.border)
+    }
+
+    pub fn text(&self) -> Color {
+        parse(&self.text)
+    }
+
+    pub fn cells(&self) -> Cells {
+        let unicode = utf8_locale();
+        let glyphs = match self.glyphs {
+            Glyphs::Blocks | Glyphs::Half if !unicode => Glyphs::Ascii,
+            glyphs => glyphs,
+        };
+        Cells {
+            width: match glyphs {
+                Glyphs::Letters => 3,
+                Glyphs::Half => 1,
+                _ => 2,
+            },
+            unicode,
+            glyphs,
+            empty: self.empty,
+        }
+    }
+}
+
+/// whether the locale says the terminal takes UTF-8, going by the variables that set it in order.
+/// with none of them set there's only the C locale, except on Windows where terminals are UTF-8
+fn utf8_locale() -> bool {
+    let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .find_map(|var| std::env::var(var).ok().filter(|value| !value.is_empty()));
+    match locale {
+        Some(locale) => {
+            let locale = locale.to_lowercase();
+            locale.contains("utf-8") || locale.contains("utf8")
+        }
+        None => cfg!(windows),
+    }
+}
+
+/// a hand-edited config can still have a bad colour in it, which is drawn white
+fn parse(color: &str) -> Color {
+    Color::from_str(color).unwrap_or(Color::White)
+}
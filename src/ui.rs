@@ -1,44 +1,192 @@
 use ratatui::{
+    layout::{Constraint, Layout, Rect},
     style::{Color, Stylize},
-    text::{Line, Text},
+    text::{Line, Span, Text},
     widgets::Paragraph,
     Frame,
 };
 
-use crate::{block::Block, constants::{ROW_HEIGHT, ROW_WIDTH}, grid::Grid, shape::Shape};
-
-pub fn draw(frame: &mut Frame, grid: Grid) {
-    let mut lines = Vec::with_capacity(ROW_HEIGHT);
-    for row in &grid.rows {
-        let mut line = Line::raw("");
-        for cell in &row.cells {
-            let styled_cell = match cell {
-                Block::Active(shape) | Block::Full(shape) => match shape {
-                    Shape::I => cell.repr().fg(Color::Red),
-                    Shape::O => cell.repr().fg(Color::Blue),
-                    Shape::T => cell.repr().fg(Color::Rgb(255, 165, 0)),
-                    Shape::S => cell.repr().fg(Color::Green),
-                    Shape::Z => cell.repr().fg(Color::Cyan),
-                    Shape::J => cell.repr().fg(Color::White),
-                    Shape::L => cell.repr().fg(Color::Magenta),
+use crate::{block::Block, config::{key_name, Config}, grid::Grid, master, mode::Mode, rules::Goal, shape::Shape, speed::FRAMES_PER_SECOND, stats::{self, Stat}, theme::{Cells, Theme}};
+
+/// width of the panels next to the board
+const SIDE_WIDTH: u16 = 22;
+
+/// what blocks that just locked or were just cleared light up in
+const FLASH: Color = Color::White;
+
+/// room for the longest clear label, "T-SPIN DOUBLE" or "PERFECT CLEAR"
+const ACTION_WIDTH: u16 = 14;
+
+/// longest bar in the stats histogram, leaving room for the letter and the count
+const HISTOGRAM_WIDTH: u32 = 12;
+
+/// the board's cells and its border
+fn board_size(grid: &Grid, cells: &Cells) -> (u16, u16) {
+    ((grid.width * grid.scale) as u16 * cells.width + 2, cells.lines((grid.height * grid.scale) as u16) + 2)
+}
+
+/// the smallest area a board can be drawn in, with a status line above it and the garbage meter next to it
+pub fn min_size(grid: &Grid, theme: &Theme) -> (u16, u16) {
+    let (width, height) = board_size(grid, &theme.cells());
+    (width + 1, height + 1)
+}
+
+/// what a click on the game screen landed on
+pub enum Spot {
+    /// a column of the board
+    Board(usize),
+    Help,
+    Info,
+    Hold,
+}
+
+/// where everything goes, panels that don't fit are left empty
+#[derive(Default)]
+struct Panels {
+    board: Rect,
+    status: Rect,
+    help: Rect,
+    hold: Rect,
+    next: Rect,
+    info: Rect,
+    stats: Rect,
+    notes: Rect,
+}
+
+/// the panels on both sides of the board when there's room, then on one side with the help left out,
+/// then just a status line above the board
+fn panels(area: Rect, grid: &Grid, cfg: &Config) -> Panels {
+    let theme = &cfg.theme;
+    let info = info(grid).len() as u16;
+    let stats = stats_text(grid, cfg).len() as u16;
+    let hold = held_shape_text(grid, theme).len() as u16;
+    let next = next_shape_text(grid, theme).len() as u16;
+    let (board_width, board_height) = board_size(grid, &theme.cells());
+    let board_width = board_width + 1;
+
+    if area.width >= board_width + SIDE_WIDTH * 2 && area.height >= board_height {
+        let [left, board, right] =
+            Layout::horizontal([Constraint::Length(SIDE_WIDTH), Constraint::Fill(1), Constraint::Length(SIDE_WIDTH)]).areas(area);
+        let [help, hold, next] =
+            Layout::vertical([Constraint::Fill(1), Constraint::Length(hold), Constraint::Length(next)]).areas(inset(left));
+        let [info, stats, notes] =
+            Layout::vertical([Constraint::Length(info + 1), Constraint::Length(stats), Constraint::Fill(1)]).areas(inset(right));
+        Panels { board, help, hold, next, info, stats, notes, ..Panels::default() }
+    } else if area.width >= board_width + SIDE_WIDTH && area.height >= board_height {
+        let [board, right] = Layout::horizontal([Constraint::Fill(1), Constraint::Length(SIDE_WIDTH)]).areas(area);
+        let [info, stats, hold, next, notes] = Layout::vertical([
+            Constraint::Length(info + 1),
+            Constraint::Length(stats),
+            Constraint::Length(hold),
+            Constraint::Length(next),
+            Constraint::Fill(1),
+        ])
+        .areas(inset(right));
+        Panels { board, hold, next, info, stats, notes, ..Panels::default() }
+    } else {
+        let [status, board] = Layout::vertical([Constraint::Length(1), Constraint::Fill(1)]).areas(area);
+        Panels { board, status, ..Panels::default() }
+    }
+}
+
+/// where the board's cells go, in the middle of its panel
+fn board_cells(panel: Rect, grid: &Grid, cells: &Cells) -> Rect {
+    let width = (grid.width * grid.scale) as u16 * cells.width;
+    let height = cells.lines((grid.height * grid.scale) as u16);
+    Rect::new(
+        panel.x + panel.width.saturating_sub(width) / 2,
+        panel.y + panel.height.saturating_sub(height) / 2,
+        width,
+        height,
+    )
+}
+
+/// what's at `(column, row)` on a board drawn with `draw_in` in `area`
+pub fn spot_at(area: Rect, grid: &Grid, cfg: &Config, column: u16, row: u16) -> Option<Spot> {
+    let panels = panels(area, grid, cfg);
+    let board = board_cells(panels.board, grid, &cfg.theme.cells());
+    let position = ratatui::layout::Position::new(column, row);
+    if board.contains(position) {
+        let x = (column - board.x) / cfg.theme.cells().width / grid.scale as u16;
+        return Some(Spot::Board(x as usize));
+    }
+    [(panels.help, Spot::Help), (panels.info, Spot::Info), (panels.hold, Spot::Hold)]
+        .into_iter()
+        .find(|(panel, _)| panel.contains(position))
+        .map(|(_, spot)| spot)
+}
+
+/// `area` without the column on each side and the row at the top, so panels don't touch
+fn inset(area: Rect) -> Rect {
+    Rect::new(area.x + 1, area.y + 1, area.width.saturating_sub(2), area.height.saturating_sub(1))
+}
+
+pub fn draw(frame: &mut Frame, grid: Grid, cfg: &Config) {
+    draw_in(frame, frame.area(), grid, cfg);
+}
+
+/// like `draw`, but laid out inside `area` instead of the whole screen, e.g. one half in versus
+pub fn draw_in(frame: &mut Frame, area: Rect, grid: Grid, cfg: &Config) {
+    let theme = &cfg.theme;
+    let (min_width, min_height) = min_size(&grid, theme);
+    if area.width < min_width || area.height < min_height {
+        draw_too_small(frame, area, (min_width, min_height));
+        return;
+    }
+
+    let cells = theme.cells();
+    let board_width = (grid.width * grid.scale) as u16;
+    let board_height = (grid.height * grid.scale) as u16;
+    let ghost = grid.ghost_blocks();
+    let animations = Some(&grid.animations).filter(|_| !cfg.no_animations);
+    let now = grid.frames;
+    let rows = animations
+        .and_then(|animations| animations.clearing(&grid.rows, now))
+        .unwrap_or_else(|| grid.rows.iter().map(|row| (row.clone(), false)).collect());
+    // each cell as it's drawn with glyphs, and the colour it fills with half blocks
+    let mut board = Vec::with_capacity(board_height as usize);
+    for (y, (row, lit)) in rows.iter().enumerate() {
+        let mut line = Vec::with_capacity(board_width as usize);
+        for (x, cell) in row.cells.iter().enumerate().flat_map(|cell| std::iter::repeat_n(cell, grid.scale)) {
+            let cell = if grid.is_hidden((x, y)) { &Block::Empty } else { cell };
+            let flash = *lit || animations.is_some_and(|animations| animations.locking(x, y, now));
+            let trail = animations.and_then(|animations| animations.trail(x, y, now));
+            let styled_cell: (Span, Option<Color>) = match cell {
+                Block::Active(shape) | Block::Full(shape) if flash => (cells.block(*shape).fg(FLASH), Some(FLASH)),
+                Block::Active(shape) | Block::Full(shape) => {
+                    let color = theme.piece(&grid.rules, *shape);
+                    (cells.block(*shape).fg(color), Some(color))
+                }
+                Block::Garbage if flash => (cells.garbage().fg(FLASH), Some(FLASH)),
+                Block::Garbage => (cells.garbage().fg(theme.garbage()), Some(theme.garbage())),
+                Block::Empty if ghost.contains(&(x, y)) => (cells.ghost().fg(theme.ghost()), Some(theme.ghost())),
+                Block::Empty => match trail {
+                    Some(shape) => (cells.ghost().fg(theme.piece(&grid.rules, shape)).dim(), Some(theme.ghost())),
+                    None => (cells.empty().fg(theme.text()), None),
                 },
-                _ => cell.repr().fg(Color::White),
             };
-            line.push_span(styled_cell);
+            line.push(styled_cell);
+        }
+        for _ in 0..grid.scale {
+            board.push(line.clone());
         }
-        lines.push(Line::from(line));
     }
+    let lines = match cells.half() {
+        true => {
+            let colours = board.iter().map(|line| line.iter().map(|(_, colour)| *colour).collect()).collect::<Vec<Vec<Option<Color>>>>();
+            colours.chunks(2).map(|pair| half_line(&pair[0], pair.get(1).map_or(&[], |row| row), &cells)).collect()
+        }
+        false => board.into_iter().map(|line| line.into_iter().map(|(span, _)| span).collect()).collect::<Vec<Line>>(),
+    };
+    let board_height = cells.lines(board_height);
 
     let text = Text::from(lines);
     let paragraph = Paragraph::new(text);
 
-    let area = frame.area();
-    let centered_area = ratatui::layout::Rect::new(
-        area.x + (area.width - ROW_WIDTH as u16 * 2) / 2,
-        area.y + (area.height - ROW_HEIGHT as u16) / 2,
-        ROW_WIDTH as u16 * 2,
-        ROW_HEIGHT as u16,
-    );
+    let info_lines = info(&grid);
+    let panels = panels(area, &grid, cfg);
+
+    let centered_area = board_cells(panels.board, &grid, &cells);
 
     let outline_area = ratatui::layout::Rect::new(
         centered_area.x - 1,
@@ -48,110 +196,334 @@ pub fn draw(frame: &mut Frame, grid: Grid) {
     );
 
     frame.render_widget(
-        ratatui::widgets::Block::default().borders(ratatui::widgets::Borders::ALL),
+        ratatui::widgets::Block::default()
+            .borders(ratatui::widgets::Borders::ALL)
+            .border_set(cells.border())
+            .fg(theme.border()),
         outline_area,
     );
     frame.render_widget(paragraph, centered_area);
 
-    // Help paragraph
-    let help_text = "Controls:
-    ←: Move left
-    →: Move right
-    ↑: Rotate
-    ↓: Move down
-    q: Quit
+    // Incoming garbage warning meter, rising from the bottom next to the board
+    let pending = cells.lines(grid.garbage.pending() as u16 * grid.scale as u16).min(board_height);
+    if pending > 0 && outline_area.x > panels.board.x {
+        let meter_area = ratatui::layout::Rect::new(
+            outline_area.x - 1,
+            centered_area.y + board_height - pending,
+            1,
+            pending,
+        );
+        let meter = vec![Line::from(cells.meter()).fg(Color::Red); pending as usize];
+        frame.render_widget(Paragraph::new(meter), meter_area);
+    }
+
+    if let Some((labels, fading)) = grid.animations.action(grid.frames) {
+        draw_action(frame, labels, fading, theme, panels.board, centered_area);
+    }
+
+    frame.render_widget(Paragraph::new(next_shape_text(&grid, theme)).fg(theme.text()), panels.next);
+    frame.render_widget(Paragraph::new(held_shape_text(&grid, theme)).fg(theme.text()), panels.hold);
+    if !cfg.hide_help {
+        frame.render_widget(Paragraph::new(help_text(&grid, cfg)).fg(theme.text()), panels.help);
+    }
+    frame.render_widget(Paragraph::new(Text::from(info_lines)).fg(theme.text()), panels.info);
+    frame.render_widget(Paragraph::new(stats_text(&grid, cfg)).fg(theme.text()), panels.stats);
+
+    // with no room for the panels, the most important bits go in a line above the board
+    let mut status = format!("Score {}  Level {}", grid.score, grid.level);
+    if let Some(shape) = grid.next_shapes.front() {
+        status.push_str(&format!("  Next {}", shape.to_char()));
+    }
+    if let Some(shape) = grid.held_shape {
+        status.push_str(&format!("  Hold {}", shape.to_char()));
+    }
+    frame.render_widget(Paragraph::new(status).centered().fg(theme.text()), panels.status);
+}
+
+/// the labels for the last clear, left of the board if there's room between it and the panels,
+/// otherwise over the top of the board
+fn draw_action(frame: &mut Frame, labels: &[String], fading: bool, theme: &Theme, area: Rect, board: Rect) {
+    let lines = labels
+        .iter()
+        .map(|label| match fading {
+            true => Line::from(label.clone()).fg(theme.text()).dim(),
+            false => Line::from(label.clone()).fg(theme.text()).bold(),
+        })
+        .collect::<Vec<Line>>();
+    let height = (lines.len() as u16).min(board.height);
+
+    // a column is left free for the garbage meter
+    if board.x >= area.x + ACTION_WIDTH + 2 {
+        let action_area = Rect::new(board.x - ACTION_WIDTH - 2, board.y + (board.height - height) * 2 / 3, ACTION_WIDTH, height);
+        frame.render_widget(Paragraph::new(lines).right_aligned(), action_area);
+    } else {
+        let action_area = Rect::new(board.x, board.y + (board.height - height) / 3, board.width, height);
+        frame.render_widget(ratatui::widgets::Clear, action_area);
+        frame.render_widget(Paragraph::new(lines).centered(), action_area);
+    }
+}
+
+/// two rows of cells in one line, the top row in the upper half of each character and the bottom row,
+/// if there is one, in the lower half. each cell is the colour it's filled with, or `None` if it's
+/// empty. without unicode the halves are drawn as `"`, `.` and `#`
+fn half_line(top: &[Option<Color>], bottom: &[Option<Color>], cells: &Cells) -> Line<'static> {
+    top.iter()
+        .enumerate()
+        .map(|(x, top)| match (*top, bottom.get(x).copied().flatten(), cells.unicode) {
+            (Some(top), Some(bottom), true) => "▀".fg(top).bg(bottom),
+            (Some(top), None, true) => "▀".fg(top),
+            (None, Some(bottom), true) => "▄".fg(bottom),
+            (Some(top), Some(_), false) => "#".fg(top),
+            (Some(top), None, false) => "\"".fg(top),
+            (None, Some(bottom), false) => ".".fg(bottom),
+            (None, None, _) => Span::raw(" "),
+        })
+        .collect()
+}
+
+/// just the blocks on the board, two rows to a line, for boards drawn small
+pub fn half_board(grid: &Grid, theme: &Theme) -> Vec<Line<'static>> {
+    let colours = grid
+        .rows
+        .iter()
+        .map(|row| {
+            row.cells
+                .iter()
+                .map(|cell| match cell {
+                    Block::Full(shape) | Block::Active(shape) => Some(theme.piece(&grid.rules, *shape)),
+                    Block::Garbage => Some(theme.garbage()),
+                    Block::Empty => None,
+                })
+                .collect()
+        })
+        .collect::<Vec<Vec<Option<Color>>>>();
+    let cells = theme.cells();
+    colours.chunks(2).map(|pair| half_line(&pair[0], pair.get(1).map_or(&[], |row| row), &cells)).collect()
+}
+
+/// shown instead of a board when it doesn't fit, the games wait for a resize while it's up
+pub fn draw_too_small(frame: &mut Frame, area: Rect, (width, height): (u16, u16)) {
+    let lines = vec![
+        Line::from("Terminal too small"),
+        Line::from(format!("need {}x{}", width, height)),
+        Line::from(format!("have {}x{}", area.width, area.height)),
+    ];
+    let top = area.height.saturating_sub(lines.len() as u16) / 2;
+    let message_area = area.intersection(Rect::new(area.x, area.y + top, area.width, lines.len() as u16));
+    frame.render_widget(ratatui::widgets::Clear, area);
+    frame.render_widget(Paragraph::new(lines).centered().fg(Color::Yellow), message_area);
+}
 
-Options:
-    -l, --level: Start at a specific level (1-10)
-    ";
+/// the keys as they're bound, and the options for other games
+fn help_text(grid: &Grid, cfg: &Config) -> Vec<Line<'static>> {
+    let mut keys = vec![
+        (&cfg.move_left, "Move left"),
+        (&cfg.move_right, "Move right"),
+        (&cfg.rotate_cw, "Rotate"),
+        (&cfg.rotate_ccw, "Rotate back"),
+        (&cfg.soft_drop, "Soft drop"),
+        (&cfg.hard_drop, "Hard drop"),
+    ];
+    if grid.rules.hold {
+        keys.push((&cfg.hold, "Hold"));
+    }
+    keys.push((&cfg.pause, "Pause"));
+    keys.push((&cfg.quit, "Quit"));
 
-    // Preview of the next shape
-    let repr = grid.next_shape.color();
+    let mut help_text = vec![Line::from("Controls:")];
+    help_text.extend(keys.into_iter().map(|(key, action)| Line::from(format!("  {}: {}", key_name(key.code), action))));
+    if grid.mode.mouse_placement() {
+        help_text.extend(["  Click: move/drop", "  Wheel: rotate"].map(Line::from));
+    }
+    help_text
+}
 
+/// preview of the next shapes
+fn next_shape_text(grid: &Grid, theme: &Theme) -> Vec<Line<'static>> {
     let mut next_shape_text = vec![
-        Line::from("Next Shape:").fg(Color::White),
+        Line::from("Next Shape:"),
         Line::from(""),
     ];
 
-    next_shape_text.extend(repr);
-
-    let next_shape_paragraph = Paragraph::new(next_shape_text);
-    let next_shape_area = ratatui::layout::Rect::new(
-        area.x + 1,
-        area.y + area.height - 10,
-        20,
-        5,
-    );
-    frame.render_widget(next_shape_paragraph, next_shape_area);
+    if grid.next_shapes.is_empty() {
+        next_shape_text.push(Line::from("None"));
+    }
+    for shape in grid.next_shapes.iter().take(grid.rules.preview) {
+        next_shape_text.extend(shape_preview(grid, *shape, theme));
+        next_shape_text.push(Line::from(""));
+    }
+    next_shape_text
+}
 
-    // Display held shape
+/// the held shape, nothing at all when the rules have no hold
+fn held_shape_text(grid: &Grid, theme: &Theme) -> Vec<Line<'static>> {
+    if !grid.rules.hold {
+        return Vec::new();
+    }
     let held_shape_repr = match grid.held_shape {
-        Some(shape) => {
-            let mut lines = Vec::with_capacity(4);
-            let shape_repr = match shape {
-                Shape::I => crate::constants::I,
-                Shape::O => crate::constants::O,
-                Shape::T => crate::constants::T,
-                Shape::S => crate::constants::S,
-                Shape::Z => crate::constants::Z,
-                Shape::J => crate::constants::J,
-                Shape::L => crate::constants::L,
-            };
-            for line in shape_repr.split('\n') {
-                lines.push(Line::from(line).fg(match shape {
-                    Shape::I => Color::Red,
-                    Shape::O => Color::Blue,
-                    Shape::T => Color::Rgb(255, 165, 0),
-                    Shape::S => Color::Green,
-                    Shape::Z => Color::Cyan,
-                    Shape::J => Color::White,
-                    Shape::L => Color::Magenta,
-                }));
-            }
-            lines
-        }
-        None => vec![Line::from("None").fg(Color::White)],
+        Some(shape) => shape_preview(grid, shape, theme),
+        None => vec![Line::from("None")],
     };
 
     let mut held_shape_text = vec![
-        Line::from("Held Shape:").fg(Color::White),
+        Line::from("Held Shape:"),
         Line::from(""),
     ];
-
     held_shape_text.extend(held_shape_repr);
+    held_shape_text.push(Line::from(""));
+    held_shape_text
+}
 
-    let held_shape_paragraph = Paragraph::new(held_shape_text);
-    let held_shape_area = ratatui::layout::Rect::new(
-        area.x + 1,
-        area.y + area.height - 20,
-        20,
-        5,
-    );
-    frame.render_widget(held_shape_paragraph, held_shape_area);
-
-    let help_paragraph = Paragraph::new(help_text);
-    let help_area = ratatui::layout::Rect::new(
-        area.x + 1,
-        area.y + 1,
-        20,
-        7,
-    );
-    frame.render_widget(help_paragraph, help_area);
-
-    // Info paragraph
-    let info_text = Text::from(vec![
+/// the mode, score, level and whatever else the mode keeps track of
+fn info(grid: &Grid) -> Vec<Line<'static>> {
+    let mut info_lines = vec![
         Line::from("Tetris Game"),
+        Line::from(format!(
+            "Mode: {}{}{}",
+            grid.visibility.prefix(),
+            if grid.scale > 1 { "Big " } else { "" },
+            if grid.mode == Mode::Custom { &grid.rules.name } else { grid.mode.name() }
+        )),
         Line::from(format!("Score: {}", grid.score)),
-        Line::from(format!("Level: {}", grid.level)),
-    ]);
-    let info_paragraph = Paragraph::new(info_text);
-    let info_area = ratatui::layout::Rect::new(
-        area.x + area.width - 21,
-        area.y + 1,
-        20,
-        7,
-    );
-    frame.render_widget(info_paragraph, info_area);
+    ];
+    if grid.mode == Mode::Master {
+        info_lines.push(Line::from(format!("Level: {}/{}", grid.level, master::section_stop(grid.level) + 1)));
+        info_lines.push(Line::from(format!("Grade: {}", master::grade(grid))));
+        info_lines.push(Line::from(format!("Time: {}", master::format_time(grid.frames))));
+    } else {
+        info_lines.push(Line::from(format!("Level: {}", grid.level)));
+    }
+    match grid.rules.goal {
+        Some(Goal::Lines(lines)) => {
+            info_lines.push(Line::from(format!("Lines: {}/{}", grid.lines, lines)));
+            info_lines.push(Line::from(format!("Time: {}", master::format_time(grid.frames))));
+        }
+        Some(Goal::Time(seconds)) => {
+            let left = (seconds as u64 * FRAMES_PER_SECOND as u64).saturating_sub(grid.frames);
+            info_lines.push(Line::from(format!("Time left: {}", master::format_time(left))));
+        }
+        Some(Goal::Score(score)) => {
+            info_lines.push(Line::from(format!("Goal: {} points", score)));
+            info_lines.push(Line::from(format!("Time: {}", master::format_time(grid.frames))));
+        }
+        None => {}
+    }
+    if grid.mode == Mode::Finesse {
+        info_lines.push(Line::from(format!("Finesse: {:.1}%", grid.finesse.percentage())));
+        info_lines.push(Line::from(format!("Faults: {}", grid.finesse.faults)));
+        if let Some(fault) = &grid.finesse.last_fault {
+            if grid.finesse.just_faulted() {
+                info_lines.push(Line::from("FAULT!").fg(Color::Red));
+            }
+            info_lines.push(Line::from(format!("Pressed: {}", fault.pressed.len())));
+            info_lines.push(Line::from("Optimal:"));
+            info_lines.push(Line::from(
+                fault.optimal.iter().map(|input| input.to_string()).collect::<Vec<_>>().join(" "),
+            ));
+        }
+    }
+    info_lines
+}
+
+/// the stats picked in the config, with a blank line after them
+fn stats_text(grid: &Grid, cfg: &Config) -> Vec<Line<'static>> {
+    let mut lines = Vec::new();
+    if cfg.hide_stats {
+        return lines;
+    }
+    for stat in &cfg.stats {
+        match stat {
+            Stat::Time => lines.push(Line::from(format!("Time: {}", master::format_time(grid.frames)))),
+            Stat::Lines => lines.push(Line::from(format!("Lines: {}", grid.lines))),
+            Stat::Pieces => lines.push(Line::from(format!("Pieces: {}", grid.pieces_locked))),
+            Stat::Pps => lines.push(Line::from(format!("PPS: {:.2}", stats::pps(grid)))),
+            Stat::Kpp => lines.push(Line::from(format!("KPP: {:.2}", stats::kpp(grid)))),
+            Stat::Apm => lines.push(Line::from(format!("APM: {:.1}", stats::apm(grid)))),
+            Stat::Faults => lines.push(Line::from(format!("Faults: {}", grid.finesse.faults))),
+            Stat::Combo => lines.push(Line::from(format!("Combo: {}", grid.combo.saturating_sub(1)))),
+            Stat::B2b => lines.push(Line::from(format!("B2B: {}", if grid.back_to_back { "ready" } else { "-" }))),
+            Stat::Histogram => lines.extend(histogram(grid, &cfg.theme)),
+        }
+    }
+    if !lines.is_empty() {
+        lines.push(Line::from(""));
+    }
+    lines
+}
+
+/// a bar for each piece in the rule set, the most placed piece's as wide as the panel allows
+fn histogram(grid: &Grid, theme: &Theme) -> Vec<Line<'static>> {
+    let most = grid.stats.placed.values().copied().max().unwrap_or(0).max(1);
+    let bar = theme.cells().meter();
+    grid.rules
+        .shapes()
+        .into_iter()
+        .map(|shape| {
+            let count = grid.stats.placed.get(&shape).copied().unwrap_or(0);
+            let length = (count * HISTOGRAM_WIDTH).div_ceil(most) as usize;
+            Line::from(vec![
+                format!("{} ", shape.to_char()).into(),
+                bar.repeat(length).fg(theme.piece(&grid.rules, shape)),
+                format!(" {}", count).into(),
+            ])
+        })
+        .collect()
+}
+
+fn shape_preview(grid: &Grid, shape: Shape, theme: &Theme) -> Vec<Line<'static>> {
+    let color = theme.piece(&grid.rules, shape);
+    let cells = theme.cells();
+    match grid.rules.piece(shape) {
+        // the same half blocks as the board, so the preview keeps its shape
+        Some(piece) if cells.half() => {
+            let rows = piece.preview("#", " ");
+            let width = rows.iter().map(String::len).max().unwrap_or(0);
+            let colours = rows
+                .iter()
+                .map(|row| (0..width).map(|x| (row.as_bytes().get(x) == Some(&b'#')).then_some(color)).collect())
+                .collect::<Vec<Vec<Option<Color>>>>();
+            colours.chunks(2).map(|pair| half_line(&pair[0], pair.get(1).map_or(&[], |row| row), &cells)).collect()
+        }
+        Some(piece) => piece
+            .preview(&cells.block(shape), &cells.blank())
+            .into_iter()
+            .map(|line| Line::from(line).fg(color))
+            .collect(),
+        None => vec![Line::from(shape.to_char().to_string()).fg(color)],
+    }
 }
 
+/// extra lines under the info panel, used by modes with their own objectives
+pub fn draw_notes(frame: &mut Frame, grid: &Grid, cfg: &Config, notes: Vec<Line>) {
+    draw_notes_in(frame, frame.area(), grid, cfg, notes);
+}
+
+/// like `draw_notes`, for a board drawn with `draw_in`. the notes are left out when the panels don't fit
+pub fn draw_notes_in(frame: &mut Frame, area: Rect, grid: &Grid, cfg: &Config, notes: Vec<Line>) {
+    let (min_width, min_height) = min_size(grid, &cfg.theme);
+    if area.width < min_width || area.height < min_height {
+        return;
+    }
+    frame.render_widget(Paragraph::new(notes).fg(cfg.theme.text()), panels(area, grid, cfg).notes);
+}
 
+/// a bordered box in the middle of the screen, drawn on top of whatever is already there
+pub fn draw_message(frame: &mut Frame, lines: Vec<Line>) {
+    let area = frame.area();
+    let width = (lines.iter().map(|line| line.width()).max().unwrap_or(0) as u16 + 4).min(area.width);
+    let height = (lines.len() as u16 + 2).min(area.height);
+    let message_area = ratatui::layout::Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    );
+
+    frame.render_widget(ratatui::widgets::Clear, message_area);
+    frame.render_widget(
+        Paragraph::new(lines)
+            .centered()
+            .block(ratatui::widgets::Block::default().borders(ratatui::widgets::Borders::ALL)),
+        message_area,
+    );
+}
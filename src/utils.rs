@@ -1,39 +1,64 @@
 // This file contains utility functions for the Tetris game.
 
 use std::io::{self, Stdout};
-use crossterm::{event::{self, Event, KeyCode, KeyEventKind}, ExecutableCommand};
-use ratatui::{prelude::CrosstermBackend, Terminal};
-use crate::{cleanup_terminal, config::Config, grid::Grid, ui};
+use std::time::{Duration, Instant};
+use crossterm::{event::{self, Event, KeyCode, KeyEventKind, MouseButton, MouseEvent, MouseEventKind}, ExecutableCommand};
+use ratatui::{layout::Rect, prelude::CrosstermBackend, Terminal};
+use ratatui::text::Line;
+use crate::{block::Block, cleanup_terminal, config::Config, grid::Grid, theme::Theme, master, menu::{self, Paused}, mode::{Mode, Visibility}, ui::{self, Spot}, versus::Action};
 
-pub fn handle_events(grid: &mut Grid, mut end_cb: impl FnMut(Grid) -> (), config: Config, terminal: &mut Terminal<CrosstermBackend<Stdout>>) -> Result<bool, String> {
+/// seconds counted down before a paused game picks up again
+const COUNTDOWN: u32 = 3;
+
+/// returns whether the quit key was pressed. every input that changes the board is added to `actions`
+/// with the frame it happened on, so the game can be played back as a replay. the pause key only
+/// pauses the board, it's up to the game to open the pause menu once it sees `grid.paused`. clicking
+/// a panel can show or hide panels for the rest of the game, which is why `config` can change
+pub fn handle_events(grid: &mut Grid, mut end_cb: impl FnMut(Grid), config: &mut Config, terminal: &mut Terminal<CrosstermBackend<Stdout>>, actions: &mut Vec<(u64, Action)>) -> Result<bool, String> {
     match event::read() {
         Ok(Event::Key(key)) if key.kind == KeyEventKind::Press => match key.code {
             val if val == config.hard_drop.code => {
-                grid.paused = false;
-                while grid.bring_down(None, &mut end_cb) {}
+                actions.push((grid.frames, Action::HardDrop));
+                Action::HardDrop.apply(grid, &mut end_cb);
             },
             val if val == config.soft_drop.code => {
-                grid.paused = false;
-                while grid.bring_down(None, &mut end_cb) {
+                // the whole slide down is one key press
+                grid.stats.keys += 1;
+                loop {
+                    // one soft drop per cell, the last one finds the piece resting and may lock it
+                    actions.push((grid.frames, Action::SoftDrop));
+                    if !grid.bring_down(None, &mut end_cb) {
+                        break;
+                    }
+                    grid.soft_drop_cells += 1;
                     std::thread::sleep(std::time::Duration::from_millis(config.soft_drop_ms_per_cell as u64));
                     // redraw the screen
-                    terminal.draw(|frame| ui::draw(frame, grid.clone())).unwrap();
+                    terminal.draw(|frame| ui::draw(frame, grid.clone(), config)).unwrap();
 
                     // and also check for events so that fancy sliding can happen
                     if event::poll(std::time::Duration::from_millis(0)).unwrap() {
                         if let Ok(Event::Key(key)) = event::read() {
-                            if key.kind == KeyEventKind::Press {
-                                handle_key_event(grid, config.clone(), terminal, key.code);
+                            if key.kind == KeyEventKind::Press && handle_key_event(grid, config.clone(), key.code, actions) {
+                                return Ok(true);
                             }
                         }
                     }
+                    if grid.paused {
+                        break;
+                    }
                 }
             },
             _ => {
-                handle_key_event(grid, config.clone(), terminal, key.code);
+                if handle_key_event(grid, config.clone(), key.code, actions) {
+                    return Ok(true);
+                }
             }
 
         },
+        Ok(Event::Mouse(mouse)) => {
+            let area = terminal.get_frame().area();
+            handle_mouse(grid, end_cb, config, area, mouse, actions);
+        }
 
         Ok(_) => {}
         Err(e) => return Err(e.to_string()),
@@ -42,52 +67,215 @@ pub fn handle_events(grid: &mut Grid, mut end_cb: impl FnMut(Grid) -> (), config
     Ok(false)
 }
 
-fn handle_key_event(grid: &mut Grid, config: Config, terminal: &mut Terminal<CrosstermBackend<Stdout>>, keycode: KeyCode) {
+/// a click on the help panel shows or hides it, and one on the info panel does the same for the stats
+/// under it. a click on the hold panel holds. in the modes that wait for pieces to be placed, clicking
+/// the board moves the piece over that column and a second click on the same column drops it, even if
+/// the piece couldn't get there. the wheel and the right button rotate it and the middle button holds
+fn handle_mouse(grid: &mut Grid, mut end_cb: impl FnMut(Grid), config: &mut Config, area: Rect, mouse: MouseEvent, actions: &mut Vec<(u64, Action)>) {
+    let placing = grid.mode.mouse_placement();
+    let clicked = ui::spot_at(area, grid, config, mouse.column, mouse.row);
+    let moves = match (mouse.kind, clicked) {
+        (MouseEventKind::Down(MouseButton::Left), Some(Spot::Help)) => {
+            config.hide_help = !config.hide_help;
+            return;
+        }
+        (MouseEventKind::Down(MouseButton::Left), Some(Spot::Info)) => {
+            config.hide_stats = !config.hide_stats;
+            return;
+        }
+        (MouseEventKind::Down(MouseButton::Left), Some(Spot::Hold)) => vec![Action::Hold],
+        (MouseEventKind::Down(MouseButton::Left), Some(Spot::Board(column))) if placing => place(grid, column),
+        (MouseEventKind::Down(MouseButton::Right) | MouseEventKind::ScrollUp, _) if placing => vec![Action::RotateCw],
+        (MouseEventKind::ScrollDown, _) if placing => vec![Action::RotateCcw],
+        (MouseEventKind::Down(MouseButton::Middle), _) if placing => vec![Action::Hold],
+        _ => return,
+    };
+    for action in moves {
+        actions.push((grid.frames, action));
+        action.apply(grid, &mut end_cb);
+    }
+}
+
+/// the moves that take the active piece to be centred on `column`, or a hard drop if `column` was
+/// the last one clicked
+fn place(grid: &mut Grid, column: usize) -> Vec<Action> {
+    if grid.clicked_column.replace(column) == Some(column) {
+        return vec![Action::HardDrop];
+    }
+    let columns = grid.active_positions().into_iter().map(|(x, _)| x).collect::<Vec<usize>>();
+    let (Some(left), Some(right)) = (columns.iter().min(), columns.iter().max()) else {
+        return Vec::new();
+    };
+    let width = right - left + 1;
+    let target = column.saturating_sub((width - 1) / 2).min(grid.width - width);
+    match target.cmp(left) {
+        std::cmp::Ordering::Equal => Vec::new(),
+        std::cmp::Ordering::Less => vec![Action::Left; left - target],
+        std::cmp::Ordering::Greater => vec![Action::Right; target - left],
+    }
+}
+
+/// the keys other than the drops, returns whether it was the quit key
+pub fn handle_key_event(grid: &mut Grid, config: Config, keycode: KeyCode, actions: &mut Vec<(u64, Action)>) -> bool {
     match keycode {
-            val if val == config.move_left.code => {
-                grid.move_active_blocks(-1, 0);
-            },
-            val if val == config.move_right.code => {
-                grid.move_active_blocks(1, 0);
-            },
-            val if val == config.rotate_cw.code => {
-                grid.rotate_active_block();
-            },
-            val if val == config.rotate_ccw.code => {
-                grid.rotate_active_block();
-                grid.rotate_active_block();
-                grid.rotate_active_block();
-            },
-            val if val == config.hold.code => {
-                grid.hold();
-            },
             val if val == config.pause.code => {
-                grid.paused = !grid.paused;
+                grid.paused = true;
             },
             val if val == config.quit.code => {
-                end_game(grid);
+                return true;
             },
-            _ => {}
+            val if val == config.hard_drop.code || val == config.soft_drop.code => {},
+            val => {
+                if let Some(action) = Action::from_key(&config, val) {
+                    actions.push((grid.frames, action));
+                    action.apply(grid, |_| {});
+                }
+            }
     }
+    false
 }
 
-pub fn end_game(grid: &Grid) {
+/// how the game went, shown when it ends
+pub fn summary(grid: &Grid) -> Vec<String> {
+    let mut lines = vec![match grid.rules.goal {
+        Some(goal) if goal.reached(grid) => format!("Goal reached: {}!", goal.describe()),
+        _ => "Game Over!".to_string(),
+    }];
+    lines.push(format!("Score: {}", grid.score));
+    lines.push(format!("Level: {}", grid.level));
+    if grid.mode == Mode::Master {
+        lines.push(format!("Grade: {}", master::grade(grid)));
+        lines.push(format!("Time: {}", master::format_time(grid.frames)));
+    } else if grid.rules.goal.is_some() {
+        lines.push(format!("Lines: {}", grid.lines));
+        lines.push(format!("Time: {}", master::format_time(grid.frames)));
+    }
+    lines
+}
+
+pub fn end_game(grid: &Grid, theme: &Theme) {
     // clear the screen
     io::stdout().execute(crossterm::terminal::Clear(crossterm::terminal::ClearType::All)).unwrap();
     cleanup_terminal();
-    println!("Game Over!");
-    println!("Score: {}", grid.score);
-    println!("Level: {}", grid.level);
+    let summary = summary(grid);
+    println!("{}", summary[0]);
+    if grid.visibility != Visibility::Visible {
+        // show the whole stack now that the game is over
+        let cells = theme.cells();
+        for row in &grid.rows {
+            let row = row.cells.iter().map(|cell| match cell {
+                Block::Full(shape) | Block::Active(shape) => cells.block(*shape),
+                Block::Garbage => cells.garbage(),
+                Block::Empty => cells.empty(),
+            });
+            println!("{}", row.collect::<String>());
+        }
+    }
+    for line in &summary[1..] {
+        println!("{}", line);
+    }
     println!("Press any key to exit...");
 
     // now wait for the user to press a key
+    let _ = wait_for_key();
+
+    std::process::exit(0);
+}
+
+/// opens the pause menu once the pause key has paused `grid`, then counts down before a resumed game
+/// picks up again. the frame clock starts again afterwards, like after `wait_for_room`
+pub fn pause(terminal: &mut ratatui::DefaultTerminal, grid: &mut Grid, config: &mut Config, config_file: &str, last_frame: &mut Instant) -> Result<Paused, String> {
+    let paused = pause_with(terminal, config, config_file, last_frame, |frame, config| ui::draw(frame, grid.clone(), config))?;
+    // the stats shown may have been changed in the settings
+    grid.finesse.judged = config.shows_faults();
+    grid.paused = false;
+    Ok(paused)
+}
+
+/// how a game that can be restarted from the pause menu came to an end
+pub enum Round<T> {
+    Over(T),
+    Restarted,
+}
+
+/// plays `round` again for as long as it's restarted from the pause menu
+pub fn restarting<T>(mut round: impl FnMut() -> Result<Round<T>, String>) -> Result<T, String> {
     loop {
-        if let Ok(Event::Key(key)) = event::read() {
-            if key.kind == KeyEventKind::Press {
-                break;
+        if let Round::Over(out) = round()? {
+            return Ok(out);
+        }
+    }
+}
+
+/// the pause menu for games with more than one board, `draw` draws them under the countdown
+pub fn pause_with(
+    terminal: &mut ratatui::DefaultTerminal,
+    config: &mut Config,
+    config_file: &str,
+    last_frame: &mut Instant,
+    draw: impl Fn(&mut ratatui::Frame, &Config),
+) -> Result<Paused, String> {
+    let paused = menu::pause(terminal, config, config_file)?;
+    if paused == Paused::Resume {
+        countdown(terminal, |frame| draw(frame, config))?;
+    }
+    *last_frame = Instant::now();
+    Ok(paused)
+}
+
+/// counts down over what `draw` draws, throwing away any keys pressed meanwhile
+pub fn countdown(terminal: &mut ratatui::DefaultTerminal, draw: impl Fn(&mut ratatui::Frame)) -> Result<(), String> {
+    for count in (1..=COUNTDOWN).rev() {
+        terminal
+            .draw(|frame| {
+                draw(frame);
+                ui::draw_message(frame, vec![Line::from(count.to_string())]);
+            })
+            .map_err(|e| e.to_string())?;
+        // keys pressed during the countdown would otherwise all land at once when it ends
+        let second = Instant::now();
+        while let Some(left) = Duration::from_secs(1).checked_sub(second.elapsed()) {
+            if event::poll(left).map_err(|e| e.to_string())? {
+                event::read().map_err(|e| e.to_string())?;
             }
         }
     }
+    Ok(())
+}
 
-    std::process::exit(0);
-}
\ No newline at end of file
+/// blocks while the terminal is smaller than `needed`, which pauses the game until the window is made
+/// bigger. returns whether the quit key was pressed. the frame clock starts again afterwards, so the
+/// game doesn't race to catch up on the time spent waiting
+pub fn wait_for_room(terminal: &mut ratatui::DefaultTerminal, config: &Config, needed: (u16, u16), last_frame: &mut Instant) -> Result<bool, String> {
+    let mut waited = false;
+    loop {
+        let size = terminal.size().map_err(|e| e.to_string())?;
+        if size.width >= needed.0 && size.height >= needed.1 {
+            break;
+        }
+        waited = true;
+        terminal
+            .draw(|frame| ui::draw_too_small(frame, frame.area(), needed))
+            .map_err(|e| e.to_string())?;
+        match event::read() {
+            Ok(Event::Key(key)) if key.kind == KeyEventKind::Press && key.code == config.quit.code => return Ok(true),
+            Ok(_) => {}
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+    if waited {
+        *last_frame = Instant::now();
+    }
+    Ok(false)
+}
+
+/// blocks until a key is pressed and returns it
+pub fn wait_for_key() -> Result<KeyCode, String> {
+    loop {
+        match event::read() {
+            Ok(Event::Key(key)) if key.kind == KeyEventKind::Press => return Ok(key.code),
+            Ok(_) => {}
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+}
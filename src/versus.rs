@@ -0,0 +1,374 @@
+// src/versus.rs
+//
+// Two players share the keyboard, each with a board of their own. Lines cleared are sent to the
+// other player as garbage, which first cancels out whatever garbage is already waiting to come in.
+// Both boards are dealt the same pieces, and the first player to top out loses the round. With a
+// bot, the second board is played by the bot instead of the second player's keys.
+
+use std::cell::Cell;
+use std::time::{Duration, Instant};
+
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::Rect;
+use ratatui::style::Stylize;
+use ratatui::text::Line;
+use ratatui::widgets::Paragraph;
+use serde::{Deserialize, Serialize};
+
+use crate::bot::{Bot, BotSettings};
+use crate::clear::Clear;
+use crate::config::Config;
+use crate::finesse::Input;
+use crate::grid::Grid;
+use crate::menu::Paused;
+use crate::mode::Mode;
+use crate::rules::RuleSet;
+use crate::spectate::Publisher;
+use crate::speed::FRAMES_PER_SECOND;
+use crate::theme::Theme;
+use crate::ui;
+use crate::utils::{pause_with, restarting, wait_for_key, wait_for_room, Round};
+
+/// lines sent for clearing 0 to 4 lines at once
+const ATTACK: [u32; 5] = [0, 0, 1, 2, 4];
+/// lines sent for a t-spin clearing 0 to 3 lines
+const T_SPIN_ATTACK: [u32; 4] = [0, 2, 4, 6];
+const BACK_TO_BACK_BONUS: u32 = 1;
+const PERFECT_CLEAR_BONUS: u32 = 10;
+/// extra lines for each clear in a combo after the first, the last entry covers longer combos
+const COMBO_BONUS: [u32; 11] = [0, 1, 1, 2, 2, 3, 3, 4, 4, 4, 5];
+
+/// what a player's key press does to their board
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Action {
+    Left,
+    Right,
+    RotateCw,
+    RotateCcw,
+    Hold,
+    SoftDrop,
+    HardDrop,
+}
+
+impl Action {
+    pub const ALL: [Action; 7] = [
+        Action::Left,
+        Action::Right,
+        Action::RotateCw,
+        Action::RotateCcw,
+        Action::Hold,
+        Action::SoftDrop,
+        Action::HardDrop,
+    ];
+
+    pub fn from_key(keys: &Config, code: KeyCode) -> Option<Action> {
+        match code {
+            c if c == keys.move_left.code => Some(Action::Left),
+            c if c == keys.move_right.code => Some(Action::Right),
+            c if c == keys.rotate_cw.code => Some(Action::RotateCw),
+            c if c == keys.rotate_ccw.code => Some(Action::RotateCcw),
+            c if c == keys.hold.code => Some(Action::Hold),
+            c if c == keys.soft_drop.code => Some(Action::SoftDrop),
+            c if c == keys.hard_drop.code => Some(Action::HardDrop),
+            _ => None,
+        }
+    }
+
+    /// like `handle_events`, but a soft drop moves one cell per press so it doesn't hold up the other player
+    pub fn apply(&self, grid: &mut Grid, mut end_cb: impl FnMut(Grid)) {
+        grid.stats.keys += 1;
+        match self {
+            Action::Left => grid.shift_active(-1),
+            Action::Right => grid.shift_active(1),
+            Action::RotateCw => {
+                grid.finesse.inputs.push(Input::RotateCw);
+                grid.rotate_active_block();
+            }
+            Action::RotateCcw => {
+                grid.finesse.inputs.push(Input::RotateCcw);
+                grid.rotate_active_block_ccw();
+            }
+            Action::Hold => grid.hold(),
+            Action::SoftDrop => {
+                if grid.bring_down(None, &mut end_cb) {
+                    grid.soft_drop_cells += 1;
+                }
+            }
+            Action::HardDrop => {
+                let from = grid.active_blocks();
+                while grid.bring_down(None, &mut end_cb) {}
+                grid.lock_active(&mut end_cb);
+                grid.animations.dropped(grid.frames, &from, &grid.last_lock);
+            }
+        }
+    }
+}
+
+/// lines of garbage a clear sends to the opponent
+pub fn attack(clear: &Clear) -> u32 {
+    let lines = clear.lines as usize;
+    let base = if clear.t_spin {
+        T_SPIN_ATTACK[lines.min(T_SPIN_ATTACK.len() - 1)]
+    } else {
+        ATTACK[lines.min(ATTACK.len() - 1)]
+    };
+    if lines == 0 {
+        return base;
+    }
+
+    let combo = COMBO_BONUS[(clear.combo as usize - 1).min(COMBO_BONUS.len() - 1)];
+    let back_to_back = if clear.back_to_back { BACK_TO_BACK_BONUS } else { 0 };
+    let perfect = if clear.perfect { PERFECT_CLEAR_BONUS } else { 0 };
+    base + combo + back_to_back + perfect
+}
+
+/// passes the garbage each board has sent on to the other one
+pub fn exchange_garbage(grids: &mut [Grid; 2]) {
+    for (from, to) in [(0, 1), (1, 0)] {
+        let lines = std::mem::take(&mut grids[from].garbage.outgoing);
+        if lines > 0 {
+            grids[to].garbage.incoming.push_back(lines);
+        }
+    }
+}
+
+/// plays rounds until the players stop asking for a rematch
+pub fn play(
+    terminal: &mut ratatui::DefaultTerminal,
+    rules: RuleSet,
+    mut cfg: Config,
+    config_file: &str,
+    bot: Option<BotSettings>,
+    mut publisher: Option<Publisher>,
+) -> Result<(), String> {
+    if bot.is_none() {
+        cfg.check_versus_keys()?;
+    }
+    let mut wins = [0, 0];
+
+    loop {
+        let (grids, topped_out) = restarting(|| play_one(terminal, &rules, &mut cfg, config_file, bot, wins, &mut publisher))?;
+        let Some(topped_out) = topped_out else {
+            return Ok(());
+        };
+        // both players topping out on the same frame is a draw
+        let winner = match topped_out {
+            [true, true] => None,
+            topped_out => Some(if topped_out[0] { 1 } else { 0 }),
+        };
+        if let Some(winner) = winner {
+            wins[winner] += 1;
+        }
+
+        let message = vec![
+            Line::from(match (bot, winner) {
+                (_, None) => "Draw".to_string(),
+                (None, Some(winner)) => format!("Player {} wins!", winner + 1),
+                (Some(_), Some(0)) => "You win!".to_string(),
+                (Some(_), Some(_)) => "The bot wins".to_string(),
+            }),
+            Line::from(format!("{} - {}", wins[0], wins[1])),
+            Line::from(""),
+            Line::from("r: rematch"),
+            Line::from("any key: quit"),
+        ];
+        terminal
+            .draw(|frame| {
+                draw(frame, &grids, titles(wins, bot.is_some()), &board_keys(&cfg, bot.is_some()));
+                ui::draw_message(frame, message.clone());
+            })
+            .map_err(|e| e.to_string())?;
+
+        if wait_for_key()? != KeyCode::Char('r') {
+            return Ok(());
+        }
+    }
+}
+
+/// the final boards and which players topped out, `None` if the round was quit
+type Ending = ([Grid; 2], Option<[bool; 2]>);
+
+/// plays a round until a player tops out or it is quit
+fn play_one(
+    terminal: &mut ratatui::DefaultTerminal,
+    rules: &RuleSet,
+    cfg: &mut Config,
+    config_file: &str,
+    bot_settings: Option<BotSettings>,
+    wins: [u32; 2],
+    publisher: &mut Option<Publisher>,
+) -> Result<Round<Ending>, String> {
+    // the same seed deals both players the same pieces
+    let mut seeded = rules.clone();
+    let seed = seeded.seed.unwrap_or_else(rand::random);
+    seeded.seed = Some(seed);
+    let mut grids = [Grid::with_rules(seeded.clone(), 1), Grid::with_rules(seeded, 1)];
+    let mut bot = bot_settings.map(|settings| Bot::new(settings, seed));
+    let mut keys = player_keys(cfg, bot.is_some());
+    let topped_out = [Cell::new(false), Cell::new(false)];
+    let frame_time = Duration::from_secs(1) / FRAMES_PER_SECOND;
+    let mut last_frame = Instant::now();
+
+    for grid in grids.iter_mut() {
+        grid.mode = Mode::Versus;
+        grid.next(None);
+    }
+    // the bot's faults aren't worth the search
+    grids[0].finesse.judged = cfg.shows_faults();
+    grids[1].finesse.judged = cfg.shows_faults() && bot.is_none();
+
+    loop {
+        if wait_for_room(terminal, cfg, min_size(&grids[0], &cfg.theme), &mut last_frame)? {
+            return Ok(Round::Over((grids, None)));
+        }
+        terminal
+            .draw(|frame| draw(frame, &grids, titles(wins, bot.is_some()), &board_keys(cfg, bot.is_some())))
+            .map_err(|e| e.to_string())?;
+        if let Some(publisher) = publisher {
+            publisher.publish(&[&grids[0], &grids[1]]);
+        }
+
+        while last_frame.elapsed() >= frame_time {
+            last_frame += frame_time;
+            if let Some(bot) = &mut bot {
+                bot.act(&mut grids[1], |_| topped_out[1].set(true));
+            }
+            for (grid, topped_out) in grids.iter_mut().zip(&topped_out) {
+                grid.tick(|_| topped_out.set(true));
+            }
+            exchange_garbage(&mut grids);
+        }
+
+        while event::poll(Duration::from_millis(1)).unwrap() {
+            let code = match event::read() {
+                Ok(Event::Key(key)) if key.kind == KeyEventKind::Press => key.code,
+                Ok(_) => continue,
+                Err(e) => return Err(e.to_string()),
+            };
+
+            if code == cfg.quit.code {
+                return Ok(Round::Over((grids, None)));
+            }
+            if code == cfg.pause.code {
+                let titles = titles(wins, bot.is_some());
+                let paused = pause_with(terminal, cfg, config_file, &mut last_frame, |frame, cfg| {
+                    draw(frame, &grids, titles.clone(), &board_keys(cfg, bot.is_some()))
+                })?;
+                match paused {
+                    Paused::Resume => {}
+                    Paused::Restart => return Ok(Round::Restarted),
+                    Paused::Quit => return Ok(Round::Over((grids, None))),
+                }
+                // the keys or the stats shown may have been changed in the settings
+                keys = player_keys(cfg, bot.is_some());
+                grids[0].finesse.judged = cfg.shows_faults();
+                grids[1].finesse.judged = cfg.shows_faults() && bot.is_none();
+                continue;
+            }
+
+            for player in 0..keys.len() {
+                if let Some(action) = Action::from_key(&keys[player], code) {
+                    action.apply(&mut grids[player], |_| topped_out[player].set(true));
+                    break;
+                }
+            }
+            exchange_garbage(&mut grids);
+        }
+
+        let ended = topped_out.each_ref().map(Cell::get);
+        if ended.contains(&true) {
+            return Ok(Round::Over((grids, Some(ended))));
+        }
+    }
+}
+
+fn titles(wins: [u32; 2], bot: bool) -> [String; 2] {
+    match bot {
+        true => [format!("You ({} wins)", wins[0]), format!("Bot ({} wins)", wins[1])],
+        false => [0, 1].map(|player| format!("Player {} ({} wins)", player + 1, wins[player])),
+    }
+}
+
+/// room for both boards side by side and their titles
+pub fn min_size(grid: &Grid, theme: &Theme) -> (u16, u16) {
+    let (width, height) = ui::min_size(grid, theme);
+    (width * 2, height + 1)
+}
+
+/// the keys each human player plays with, a bot leaves the second player's keys free so they can't
+/// clash with the first player's
+fn player_keys(cfg: &Config, bot: bool) -> Vec<Config> {
+    match bot {
+        true => vec![cfg.clone()],
+        false => vec![cfg.clone(), cfg.for_player_two()],
+    }
+}
+
+/// the settings each board is drawn with, only boards played on this keyboard show their keys
+pub fn board_keys(cfg: &Config, bot: bool) -> [Config; 2] {
+    [cfg.clone(), if bot { cfg.without_help() } else { cfg.for_player_two() }]
+}
+
+/// both boards side by side, each with a title above it
+pub fn draw(frame: &mut ratatui::Frame, grids: &[Grid; 2], titles: [String; 2], keys: &[Config; 2]) {
+    let area = frame.area();
+    let half = area.width / 2;
+
+    for (player, ((grid, title), keys)) in grids.iter().zip(titles).zip(keys).enumerate() {
+        let half_area = Rect::new(area.x + half * player as u16, area.y, half, area.height);
+        let title_area = Rect::new(half_area.x, half_area.y, half_area.width, 1);
+        frame.render_widget(Paragraph::new(Line::from(title).bold()).centered(), title_area);
+
+        let board_area = Rect::new(half_area.x, half_area.y + 1, half_area.width, half_area.height.saturating_sub(1));
+        ui::draw_in(frame, board_area, grid.clone(), keys);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clear(lines: u32, t_spin: bool, combo: u32) -> Clear {
+        Clear {
+            lines,
+            rows: Vec::new(),
+            t_spin,
+            perfect: false,
+            points: 0,
+            back_to_back: false,
+            combo,
+            attack: 0,
+        }
+    }
+
+    #[test]
+    fn attack_by_lines() {
+        assert_eq!(attack(&clear(0, false, 0)), 0);
+        assert_eq!(attack(&clear(1, false, 1)), 0);
+        assert_eq!(attack(&clear(2, false, 1)), 1);
+        assert_eq!(attack(&clear(3, false, 1)), 2);
+        assert_eq!(attack(&clear(4, false, 1)), 4);
+        // more lines than a tetris, e.g. with pentominoes, send as much as a tetris
+        assert_eq!(attack(&clear(5, false, 1)), 4);
+    }
+
+    #[test]
+    fn attack_for_t_spins() {
+        assert_eq!(attack(&clear(0, true, 0)), 0);
+        assert_eq!(attack(&clear(1, true, 1)), 2);
+        assert_eq!(attack(&clear(2, true, 1)), 4);
+        assert_eq!(attack(&clear(3, true, 1)), 6);
+    }
+
+    #[test]
+    fn attack_bonuses_add_up() {
+        assert_eq!(attack(&clear(2, false, 2)), 2);
+        assert_eq!(attack(&clear(2, false, 50)), 6);
+
+        let mut tetris = clear(4, false, 1);
+        tetris.back_to_back = true;
+        assert_eq!(attack(&tetris), 5);
+        tetris.perfect = true;
+        assert_eq!(attack(&tetris), 15);
+    }
+}